@@ -0,0 +1,401 @@
+//! Safe writing of save files: atomic replacement and rotating backups.
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Name of the directory used when no backup directory is configured,
+/// created next to the save file.
+pub const DEFAULT_BACKUP_DIR_NAME: &str = "penguin-backups";
+
+/// How many backups of each file are kept unless configured otherwise.
+pub const DEFAULT_BACKUP_COUNT: usize = 10;
+
+/// Writes `bytes` to a temporary file next to `path` and renames it over `path`,
+/// so an interrupted write never leaves a half-written save behind.
+pub fn write_atomic(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(".tmp");
+
+    let temp_path = path.with_file_name(temp_name);
+
+    let result = (|| {
+        let mut temp = fs::File::create(&temp_path)?;
+        temp.write_all(bytes)?;
+        temp.sync_all()?;
+
+        fs::rename(&temp_path, path)
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+
+    result
+}
+
+/// A copy of a save file taken before it was overwritten.
+#[derive(Clone, Debug)]
+pub struct Backup {
+    pub path: PathBuf,
+    /// When the backup was taken, as `YYYY-MM-DD HH:MM:SS` (UTC).
+    pub timestamp: String,
+    pub size: u64,
+}
+
+// FNV-1a, so that the name of a backup set stays the same across builds
+fn path_hash(path: &Path) -> u64 {
+    path.to_string_lossy()
+        .bytes()
+        .fold(0xCBF2_9CE4_8422_2325, |hash, b| {
+            (hash ^ u64::from(b)).wrapping_mul(0x0100_0000_01B3)
+        })
+}
+
+/// The directory backups of `file` go to: a subdirectory of `dir` if given, otherwise
+/// of [`DEFAULT_BACKUP_DIR_NAME`] next to the file. The subdirectory is named after the
+/// file and a hash of its full path, so that saves with the same name in different
+/// places never share, prune or restore each other's backups.
+pub fn backup_dir(file: &Path, dir: Option<&Path>) -> PathBuf {
+    let base = match dir {
+        Some(d) => d.to_path_buf(),
+        None => file
+            .parent()
+            .unwrap_or(Path::new("."))
+            .join(DEFAULT_BACKUP_DIR_NAME),
+    };
+
+    let source = fs::canonicalize(file)
+        .or_else(|_| std::path::absolute(file))
+        .unwrap_or_else(|_| file.to_path_buf());
+
+    let name = file.file_name().unwrap_or_default().to_string_lossy();
+
+    base.join(format!("{}-{:016x}", name, path_hash(&source)))
+}
+
+// backups are named `<stem>-YYYYMMDD-HHMMSS[-N].<ext>`
+fn stem_and_extension(file: &Path) -> (String, String) {
+    let stem = file
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    let extension = file
+        .extension()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    (stem, extension)
+}
+
+// converts days since 1970-01-01 to a (year, month, day) civil date
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    (year, month, day)
+}
+
+fn file_timestamp(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+
+    let (year, month, day) = civil_from_days(secs.div_euclid(86400));
+    let rem = secs.rem_euclid(86400);
+
+    format!(
+        "{:04}{:02}{:02}-{:02}{:02}{:02}",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+// turns the `YYYYMMDD-HHMMSS` part of a backup name into something readable
+fn display_timestamp(stamp: &str) -> Option<String> {
+    let b = stamp.as_bytes();
+
+    let digits = b.iter().enumerate().all(|(i, c)| {
+        if i == 8 {
+            *c == b'-'
+        } else {
+            c.is_ascii_digit()
+        }
+    });
+
+    if b.len() != 15 || !digits {
+        return None;
+    }
+
+    Some(format!(
+        "{}-{}-{} {}:{}:{}",
+        &stamp[0..4],
+        &stamp[4..6],
+        &stamp[6..8],
+        &stamp[9..11],
+        &stamp[11..13],
+        &stamp[13..15]
+    ))
+}
+
+// every backup of `file` in `dir` with its timestamp and counter, newest first
+fn scan_backups(file: &Path, dir: &Path) -> io::Result<Vec<(String, usize, Backup)>> {
+    let (stem, extension) = stem_and_extension(file);
+    let prefix = format!("{}-", stem);
+
+    let entries = match fs::read_dir(dir) {
+        Ok(e) => e,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let mut found = Vec::new();
+
+    for entry in entries {
+        let entry = entry?;
+        let path = entry.path();
+
+        if path.extension().unwrap_or_default().to_string_lossy() != extension {
+            continue;
+        }
+
+        let Some(name) = path.file_stem().map(|s| s.to_string_lossy().into_owned()) else {
+            continue;
+        };
+
+        let Some(rest) = name.strip_prefix(&prefix) else {
+            continue;
+        };
+
+        if !rest.is_char_boundary(15) {
+            continue;
+        }
+
+        let (stamp, counter) = rest.split_at(15);
+
+        let counter = match counter.strip_prefix('-') {
+            Some(n) => match n.parse::<usize>() {
+                Ok(n) => n,
+                Err(_) => continue,
+            },
+            None if counter.is_empty() => 0,
+            None => continue,
+        };
+
+        let Some(timestamp) = display_timestamp(stamp) else {
+            continue;
+        };
+
+        let backup = Backup {
+            path,
+            timestamp,
+            size: entry.metadata()?.len(),
+        };
+
+        found.push((stamp.to_string(), counter, backup));
+    }
+
+    found.sort_by(|a, b| (&b.0, b.1).cmp(&(&a.0, a.1)));
+
+    Ok(found)
+}
+
+/// Every backup of `file` in `dir`, newest first.
+pub fn list_backups(file: &Path, dir: &Path) -> io::Result<Vec<Backup>> {
+    let found = scan_backups(file, dir)?;
+
+    Ok(found.into_iter().map(|(_, _, backup)| backup).collect())
+}
+
+/// Copies `file` into `dir` with a timestamp in its name, then deletes the oldest
+/// backups so that at most `keep` remain. Does nothing if `file` does not exist yet
+/// or `keep` is 0. Returns the path of the new backup.
+pub fn create_backup(file: &Path, dir: &Path, keep: usize) -> io::Result<Option<PathBuf>> {
+    if keep == 0 || !fs::exists(file)? {
+        return Ok(None);
+    }
+
+    fs::create_dir_all(dir)?;
+
+    let (stem, extension) = stem_and_extension(file);
+    let stamp = file_timestamp(SystemTime::now());
+
+    let name = |n: usize| {
+        let counter = if n == 0 {
+            String::new()
+        } else {
+            format!("-{}", n)
+        };
+
+        if extension.is_empty() {
+            format!("{}-{}{}", stem, stamp, counter)
+        } else {
+            format!("{}-{}{}.{}", stem, stamp, counter, extension)
+        }
+    };
+
+    // more than one save within a second gets a counter instead of replacing a backup
+    let n = scan_backups(file, dir)?
+        .iter()
+        .filter(|(s, _, _)| *s == stamp)
+        .map(|(_, n, _)| n + 1)
+        .max()
+        .unwrap_or(0);
+
+    let path = dir.join(name(n));
+
+    fs::copy(file, &path)?;
+
+    for old in list_backups(file, dir)?.iter().skip(keep) {
+        fs::remove_file(&old.path)?;
+    }
+
+    Ok(Some(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a fresh directory under the system temp dir, removed again by the test
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("penguin-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        dir
+    }
+
+    #[test]
+    fn timestamps_are_named_and_shown_in_utc() {
+        // 2024-02-29 12:34:56 UTC
+        let time = UNIX_EPOCH + std::time::Duration::from_secs(1_709_210_096);
+        let stamp = file_timestamp(time);
+
+        assert_eq!(stamp, "20240229-123456");
+        assert_eq!(
+            display_timestamp(&stamp).as_deref(),
+            Some("2024-02-29 12:34:56")
+        );
+        assert_eq!(display_timestamp("2024022-9123456"), None);
+    }
+
+    #[test]
+    fn backups_are_named_after_the_file() {
+        let dir = temp_dir("naming");
+        let file = dir.join("nsmb.sav");
+        fs::write(&file, b"save").unwrap();
+
+        let backups = backup_dir(&file, Some(&dir.join("backups")));
+        let path = create_backup(&file, &backups, 3).unwrap().unwrap();
+
+        let name = path.file_name().unwrap().to_string_lossy().into_owned();
+        assert!(
+            name.starts_with("nsmb-") && name.ends_with(".sav"),
+            "{}",
+            name
+        );
+        assert_eq!(fs::read(&path).unwrap(), b"save");
+        assert_eq!(list_backups(&file, &backups).unwrap().len(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn pruning_keeps_the_newest_backups() {
+        let dir = temp_dir("pruning");
+        let file = dir.join("nsmb.sav");
+        let backups = backup_dir(&file, None);
+
+        for i in 0..5u8 {
+            fs::write(&file, [i]).unwrap();
+            create_backup(&file, &backups, 3).unwrap();
+        }
+
+        let kept: Vec<Vec<u8>> = list_backups(&file, &backups)
+            .unwrap()
+            .iter()
+            .map(|b| fs::read(&b.path).unwrap())
+            .collect();
+
+        assert_eq!(kept, vec![vec![4], vec![3], vec![2]]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn saves_with_the_same_name_keep_separate_backups() {
+        let dir = temp_dir("sources");
+        let shared = dir.join("backups");
+
+        let a = dir.join("a").join("nsmb.sav");
+        let b = dir.join("b").join("nsmb.sav");
+
+        for file in [&a, &b] {
+            fs::create_dir_all(file.parent().unwrap()).unwrap();
+            fs::write(file, file.to_string_lossy().as_bytes()).unwrap();
+        }
+
+        let a_dir = backup_dir(&a, Some(&shared));
+        let b_dir = backup_dir(&b, Some(&shared));
+        assert_ne!(a_dir, b_dir);
+
+        create_backup(&a, &a_dir, 1).unwrap();
+        create_backup(&b, &b_dir, 1).unwrap();
+        create_backup(&a, &a_dir, 1).unwrap();
+
+        let listed = list_backups(&b, &b_dir).unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(
+            fs::read(&listed[0].path).unwrap(),
+            b.to_string_lossy().as_bytes()
+        );
+        assert_eq!(list_backups(&a, &a_dir).unwrap().len(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn write_atomic_replaces_the_file_without_leftovers() {
+        let dir = temp_dir("atomic");
+        let file = dir.join("nsmb.sav");
+
+        write_atomic(&file, b"first").unwrap();
+        write_atomic(&file, b"second").unwrap();
+
+        assert_eq!(fs::read(&file).unwrap(), b"second");
+
+        let names: Vec<_> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        assert_eq!(names, vec![std::ffi::OsString::from("nsmb.sav")]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn write_atomic_leaves_no_temp_file_on_failure() {
+        let dir = temp_dir("atomic-fail");
+        let missing = dir.join("missing").join("nsmb.sav");
+
+        assert!(write_atomic(&missing, b"data").is_err());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,772 @@
+use anyhow::{anyhow, Context, Result};
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use penguin::backup::write_atomic;
+use penguin::savefile::{
+    checksum::{repair_checksums, CrcRegion, CrcReport},
+    constants::*,
+    fields::Field,
+    get_slot_string,
+    presets::{builtin_presets, load_presets, Preset},
+    savediff::{diff, render_json, render_text},
+    savejson::JSON_SCHEMA,
+    savemerge::{merge, MergeStrategy},
+    saveslot::SaveSlot,
+    savevalidate::{self, crc_problems, Severity},
+    stages::world_stages,
+    SaveFile,
+};
+
+const USAGE: &str = "\
+Usage: penguin-cli <command> [arguments]
+
+Commands:
+  new <region> -o OUT                  Create a fresh save: NTSC, PAL, JPN, KOR, CHN or TW
+  info <file>                          Summarise a save file
+  dump <file> [--header | --slot N]    Print every field
+  get <file> <path>                    Print a single field
+  set <file> <path> <value> [-o OUT]   Change a single field
+  copy-slot <file> <from> <to> [-o OUT]
+                                       Copy one slot over another
+  swap-slot <file> <a> <b> [-o OUT]    Exchange two slots
+  clear-slot <file> <slot> [-o OUT]    Reset a slot to the empty state
+  promote-slot <file> <slot> [-o OUT]  Turn a quick save (3-5) into a normal save
+  new-game <file> <slot> [-o OUT]      Reset a slot to the state of a freshly started game
+  sync-flags <file> [-o OUT]           Recompute the goal, star coin and game completed flags
+                                       of every slot from its stages
+  presets [--presets P]                List the progress presets
+  preset <file> <slot> <name> [--presets P] [-o OUT]
+                                       Set a slot's story progress from a preset. --presets adds
+                                       the presets defined in a json file
+  fix-crc <file> [-o OUT]              Rewrite all checksums, leaving the data as-is
+  export <file> [-o OUT]               Write the save as json (to stdout by default)
+  import <json> -o OUT                 Build a save from a json document
+  schema                               Print the json schema
+  verify <file> [--json] [--strict]    Check checksums and look for values the game never writes.
+                                       Fails on errors, and on warnings too with --strict
+  check <file> [--json] [--fix] [-o OUT]
+                                       Look for progress the game can't reach, such as a world
+                                       unlocked too early. --fix applies the suggested fixes
+  diff <old> <new> [--json]            List every field that differs between two saves
+  merge <base> <ours> <theirs> -o OUT [--strategy S]
+                                       Combine the changes two saves made to a common base.
+                                       Conflicting fields fail the merge unless a strategy
+                                       is given: ours, theirs, base or highest
+
+Slots are numbered 0-5; 0-2 are the save slots and 3-5 are the quick save slots.
+Field paths look like `header.last_selected_index`, `slot[0].player_lives[2]`
+or `slot[1].stage_completion_flags[3][21]`. Run `dump` to see all of them.
+Commands that modify a file write it in place unless -o/--output is given.
+
+Exit codes: 0 on success, 1 if the command failed, 2 on invalid usage.";
+
+enum CliError {
+    Usage(String),
+    Failed(anyhow::Error),
+}
+
+impl From<anyhow::Error> for CliError {
+    fn from(e: anyhow::Error) -> Self {
+        CliError::Failed(e)
+    }
+}
+
+struct Args {
+    positional: Vec<String>,
+    output: Option<PathBuf>,
+    slot: Option<usize>,
+    header: bool,
+    json: bool,
+    strict: bool,
+    fix: bool,
+    presets: Option<PathBuf>,
+    strategy: Option<MergeStrategy>,
+}
+
+impl Args {
+    fn parse(raw: &[String]) -> Result<Self, CliError> {
+        let mut args = Self {
+            positional: Vec::new(),
+            output: None,
+            slot: None,
+            header: false,
+            json: false,
+            strict: false,
+            fix: false,
+            presets: None,
+            strategy: None,
+        };
+
+        let mut iter = raw.iter();
+
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "-o" | "--output" => {
+                    let value = iter
+                        .next()
+                        .ok_or_else(|| CliError::Usage(format!("{} needs a path", arg)))?;
+                    args.output = Some(PathBuf::from(value));
+                }
+
+                "--presets" => {
+                    let value = iter
+                        .next()
+                        .ok_or_else(|| CliError::Usage(String::from("--presets needs a path")))?;
+                    args.presets = Some(PathBuf::from(value));
+                }
+
+                "--slot" => {
+                    let value = iter
+                        .next()
+                        .ok_or_else(|| CliError::Usage(String::from("--slot needs a number")))?;
+                    args.slot = Some(parse_slot(value)?);
+                }
+
+                "--strategy" => {
+                    let value = iter.next().and_then(|s| MergeStrategy::from_name(s));
+                    args.strategy = Some(value.ok_or_else(|| {
+                        CliError::Usage(String::from(
+                            "--strategy needs one of ours, theirs, base or highest",
+                        ))
+                    })?);
+                }
+
+                "--header" => args.header = true,
+                "--json" => args.json = true,
+                "--strict" => args.strict = true,
+                "--fix" => args.fix = true,
+
+                _ => args.positional.push(arg.clone()),
+            }
+        }
+
+        Ok(args)
+    }
+
+    /// Checks the number of positional arguments, not counting the command itself.
+    fn expect(&self, count: usize) -> Result<(), CliError> {
+        if self.positional.len() != count {
+            return Err(CliError::Usage(format!(
+                "expected {} argument(s), got {}",
+                count,
+                self.positional.len()
+            )));
+        }
+
+        Ok(())
+    }
+
+    fn file(&self) -> &Path {
+        Path::new(&self.positional[0])
+    }
+
+    fn output(&self) -> &Path {
+        self.output.as_deref().unwrap_or(self.file())
+    }
+}
+
+fn parse_slot(s: &str) -> Result<usize, CliError> {
+    match s.parse::<usize>() {
+        Ok(i) if i < SAVE_SLOT_COUNT => Ok(i),
+        _ => Err(CliError::Usage(format!(
+            "{:?} is not a slot number (0-{})",
+            s,
+            SAVE_SLOT_COUNT - 1
+        ))),
+    }
+}
+
+fn parse_region(s: &str) -> Result<SaveFileRegion, CliError> {
+    SaveFileRegion::ALL
+        .into_iter()
+        .find(|r| format!("{:?}", r).eq_ignore_ascii_case(s))
+        .ok_or_else(|| {
+            CliError::Usage(format!(
+                "{:?} is not a region (NTSC, PAL, JPN, KOR, CHN or TW)",
+                s
+            ))
+        })
+}
+
+fn open(path: &Path) -> Result<SaveFile> {
+    SaveFile::from_path(path).with_context(|| format!("failed to open {}", path.display()))
+}
+
+fn write(path: &Path, bytes: &[u8]) -> Result<()> {
+    write_atomic(path, bytes).with_context(|| format!("failed to write {}", path.display()))
+}
+
+/// Prints lines to stdout. A closed pipe (e.g. `| head`) is not treated as an error.
+fn emit(lines: &[String]) -> Result<()> {
+    let mut stdout = io::stdout().lock();
+
+    for line in lines {
+        match writeln!(stdout, "{}", line) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => return Ok(()),
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    Ok(())
+}
+
+fn print_fields(fields: &[Field]) -> Result<()> {
+    let lines: Vec<String> = fields
+        .iter()
+        .map(|f| format!("{} = {}", f.path, f.display_value()))
+        .collect();
+
+    emit(&lines)
+}
+
+fn crc_summary(report: &CrcReport) -> String {
+    report
+        .checks()
+        .map(|c| {
+            let region = match c.region {
+                CrcRegion::Header => String::from("header"),
+                CrcRegion::Slot(i) => format!("slot {}", i),
+            };
+
+            if c.is_valid() {
+                format!("{} ok", region)
+            } else {
+                format!(
+                    "{} MISMATCH (stored {:08X}, expected {:08X})",
+                    region, c.stored, c.expected
+                )
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn slot_summary(slot: &SaveSlot) -> String {
+    if slot.is_empty() {
+        return String::from("empty");
+    }
+
+    // only stages that exist, so leftovers in unused indices don't count
+    let courses: Vec<u32> = (0..ACTUAL_WORLD_COUNT)
+        .flat_map(|w| world_stages(w).map(move |info| (w, info)))
+        .filter(|(_, info)| info.star_coins)
+        .map(|(w, info)| slot.stage_completion_flags[w][info.stage])
+        .collect();
+
+    let goals = courses
+        .iter()
+        .filter(|f| **f & StageCompletionFlags::GoalNormal.bits() != 0)
+        .count();
+
+    let star_coin_mask = (StageCompletionFlags::StarCoin1
+        | StageCompletionFlags::StarCoin2
+        | StageCompletionFlags::StarCoin3)
+        .bits();
+
+    let star_coins: u32 = courses
+        .iter()
+        .map(|f| (*f & star_coin_mask).count_ones())
+        .sum();
+
+    format!(
+        "world {}, score {}, lives {:?}, {} stages cleared, {} star coins",
+        slot.cur_world + 1,
+        slot.ingame_score,
+        slot.player_lives,
+        goals,
+        star_coins
+    )
+}
+
+fn info(args: &Args) -> Result<(), CliError> {
+    args.expect(1)?;
+
+    let bytes = fs::read(args.file())
+        .with_context(|| format!("failed to read {}", args.file().display()))?;
+    let (file, report) = SaveFile::from_bytes_checked(&bytes)
+        .with_context(|| format!("failed to open {}", args.file().display()))?;
+
+    let mut lines = vec![
+        format!("File: {}", args.file().display()),
+        format!("Region: {:?}", file.header.region),
+        format!(
+            "Last selected: {}",
+            get_slot_string(file.header.last_selected_index as usize)
+        ),
+        format!("Checksums: {}", crc_summary(&report)),
+    ];
+
+    for (i, slot) in file.save_slots.iter().enumerate() {
+        lines.push(format!(
+            "[{}] {}: {}",
+            i,
+            get_slot_string(i),
+            slot_summary(slot)
+        ));
+    }
+
+    emit(&lines)?;
+
+    Ok(())
+}
+
+fn dump(args: &Args) -> Result<(), CliError> {
+    args.expect(1)?;
+
+    let file = open(args.file())?;
+
+    if args.header {
+        print_fields(&file.header_fields())?;
+    } else if let Some(slot) = args.slot {
+        print_fields(&file.slot_fields(slot))?;
+    } else {
+        print_fields(&file.fields())?;
+    }
+
+    Ok(())
+}
+
+fn get(args: &Args) -> Result<(), CliError> {
+    args.expect(2)?;
+
+    let file = open(args.file())?;
+    let field = file
+        .get_field(&args.positional[1])
+        .map_err(|e| anyhow!(e))?;
+
+    emit(&[field.display_value()])?;
+
+    Ok(())
+}
+
+fn set(args: &Args) -> Result<(), CliError> {
+    args.expect(3)?;
+
+    let mut file = open(args.file())?;
+    file.set_field(&args.positional[1], &args.positional[2])
+        .map_err(|e| anyhow!(e))?;
+
+    write(args.output(), &file.to_bytes())?;
+
+    Ok(())
+}
+
+fn copy_slot(args: &Args) -> Result<(), CliError> {
+    args.expect(3)?;
+
+    let from = parse_slot(&args.positional[1])?;
+    let to = parse_slot(&args.positional[2])?;
+
+    let mut file = open(args.file())?;
+    file.copy_slot(from, to).map_err(|e| anyhow!(e))?;
+
+    write(args.output(), &file.to_bytes())?;
+
+    Ok(())
+}
+
+fn swap_slot(args: &Args) -> Result<(), CliError> {
+    args.expect(3)?;
+
+    let a = parse_slot(&args.positional[1])?;
+    let b = parse_slot(&args.positional[2])?;
+
+    let mut file = open(args.file())?;
+    file.swap_slots(a, b).map_err(|e| anyhow!(e))?;
+
+    write(args.output(), &file.to_bytes())?;
+
+    Ok(())
+}
+
+fn clear_slot(args: &Args) -> Result<(), CliError> {
+    args.expect(2)?;
+
+    let slot = parse_slot(&args.positional[1])?;
+
+    let mut file = open(args.file())?;
+    file.clear_slot(slot).map_err(|e| anyhow!(e))?;
+
+    write(args.output(), &file.to_bytes())?;
+
+    Ok(())
+}
+
+fn new_game(args: &Args) -> Result<(), CliError> {
+    args.expect(2)?;
+
+    let slot = parse_slot(&args.positional[1])?;
+
+    let mut file = open(args.file())?;
+    file.start_new_game(slot).map_err(|e| anyhow!(e))?;
+
+    write(args.output(), &file.to_bytes())?;
+
+    Ok(())
+}
+
+fn promote_slot(args: &Args) -> Result<(), CliError> {
+    args.expect(2)?;
+
+    let slot = parse_slot(&args.positional[1])?;
+
+    let mut file = open(args.file())?;
+    file.promote_quick_save(slot).map_err(|e| anyhow!(e))?;
+
+    write(args.output(), &file.to_bytes())?;
+
+    Ok(())
+}
+
+fn sync_flags(args: &Args) -> Result<(), CliError> {
+    args.expect(1)?;
+
+    let mut file = open(args.file())?;
+    let changed = file.sync_completion_flags();
+
+    write(args.output(), &file.to_bytes())?;
+    emit(&[format!("{} slot(s) changed", changed)])?;
+
+    Ok(())
+}
+
+// the built-in presets followed by the ones from --presets
+fn all_presets(args: &Args) -> Result<Vec<Preset>> {
+    let mut presets = builtin_presets();
+
+    if let Some(path) = &args.presets {
+        let input = fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+
+        presets.extend(
+            load_presets(&input)
+                .with_context(|| format!("failed to load presets from {}", path.display()))?,
+        );
+    }
+
+    Ok(presets)
+}
+
+fn list_presets(args: &Args) -> Result<(), CliError> {
+    args.expect(0)?;
+
+    let lines: Vec<String> = all_presets(args)?
+        .iter()
+        .map(|p| format!("{:<28}{}", p.name, p.description))
+        .collect();
+
+    emit(&lines)?;
+
+    Ok(())
+}
+
+fn apply_preset(args: &Args) -> Result<(), CliError> {
+    args.expect(3)?;
+
+    let slot = parse_slot(&args.positional[1])?;
+    let name = &args.positional[2];
+
+    let presets = all_presets(args)?;
+    let Some(preset) = presets.iter().find(|p| p.name.eq_ignore_ascii_case(name)) else {
+        return Err(CliError::Usage(format!(
+            "{:?} is not a preset; run `presets` to list them",
+            name
+        )));
+    };
+
+    let mut file = open(args.file())?;
+    file.apply_preset(slot, preset).map_err(|e| anyhow!(e))?;
+
+    write(args.output(), &file.to_bytes())?;
+
+    Ok(())
+}
+
+fn fix_crc(args: &Args) -> Result<(), CliError> {
+    args.expect(1)?;
+
+    // works on the raw bytes so that nothing but the checksums can change
+    let mut bytes = fs::read(args.file())
+        .with_context(|| format!("failed to read {}", args.file().display()))?;
+
+    let report = CrcReport::from_bytes(&bytes)
+        .with_context(|| format!("failed to open {}", args.file().display()))?;
+
+    repair_checksums(&mut bytes).map_err(|e| anyhow!(e))?;
+    write(args.output(), &bytes)?;
+
+    let fixed = report.checks().filter(|c| !c.is_valid()).count();
+    emit(&[format!("{} checksum(s) repaired", fixed)])?;
+
+    Ok(())
+}
+
+fn export(args: &Args) -> Result<(), CliError> {
+    args.expect(1)?;
+
+    let file = open(args.file())?;
+    let json = file.to_json_string();
+
+    match &args.output {
+        Some(path) => write(path, json.as_bytes())?,
+        None => emit(&[json])?,
+    }
+
+    Ok(())
+}
+
+fn new_file(args: &Args) -> Result<(), CliError> {
+    args.expect(1)?;
+
+    let Some(output) = &args.output else {
+        return Err(CliError::Usage(String::from("new needs -o/--output")));
+    };
+
+    let region = parse_region(&args.positional[0])?;
+
+    write(output, &SaveFile::new(region).to_bytes())?;
+
+    Ok(())
+}
+
+fn import(args: &Args) -> Result<(), CliError> {
+    args.expect(1)?;
+
+    let Some(output) = &args.output else {
+        return Err(CliError::Usage(String::from("import needs -o/--output")));
+    };
+
+    let input = fs::read_to_string(args.file())
+        .with_context(|| format!("failed to read {}", args.file().display()))?;
+    let file = SaveFile::from_json(&input)
+        .with_context(|| format!("failed to import {}", args.file().display()))?;
+
+    write(output, &file.to_bytes())?;
+
+    Ok(())
+}
+
+fn verify(args: &Args) -> Result<(), CliError> {
+    args.expect(1)?;
+
+    let bytes = fs::read(args.file())
+        .with_context(|| format!("failed to read {}", args.file().display()))?;
+    let (file, report) = SaveFile::from_bytes_checked(&bytes)
+        .with_context(|| format!("failed to open {}", args.file().display()))?;
+
+    let mut problems = crc_problems(&report);
+    problems.extend(file.validate());
+
+    if args.json {
+        let mut document = savevalidate::render_json(&problems);
+        document["file"] = args.file().display().to_string().into();
+
+        emit(&[json::stringify_pretty(document, 2)])?;
+    } else if problems.is_empty() {
+        emit(&[String::from("No problems found.")])?;
+    } else {
+        emit(&[savevalidate::render_text(&problems)])?;
+    }
+
+    let failed = problems
+        .iter()
+        .any(|p| p.severity == Severity::Error || args.strict);
+
+    if failed {
+        let errors = problems
+            .iter()
+            .filter(|p| p.severity == Severity::Error)
+            .count();
+
+        return Err(CliError::Failed(anyhow!(
+            "{} error(s), {} warning(s)",
+            errors,
+            problems.len() - errors
+        )));
+    }
+
+    Ok(())
+}
+
+fn check(args: &Args) -> Result<(), CliError> {
+    args.expect(1)?;
+
+    let mut file = open(args.file())?;
+    let findings = file.check_consistency();
+    let problems: Vec<_> = findings.iter().map(|f| f.problem.clone()).collect();
+
+    if args.json {
+        let mut document = savevalidate::render_json(&problems);
+        document["file"] = args.file().display().to_string().into();
+
+        for (i, finding) in findings.iter().enumerate() {
+            document["problems"][i]["fix"] = finding.fix.map(|f| f.description()).into();
+        }
+
+        emit(&[json::stringify_pretty(document, 2)])?;
+    } else if findings.is_empty() {
+        emit(&[String::from("No problems found.")])?;
+    } else {
+        let lines: Vec<String> = findings
+            .iter()
+            .map(|f| {
+                let line = savevalidate::render_text(std::slice::from_ref(&f.problem));
+
+                match f.fix {
+                    Some(fix) => format!("{} [fix: {}]", line, fix.description()),
+                    None => line,
+                }
+            })
+            .collect();
+
+        emit(&lines)?;
+    }
+
+    if args.fix {
+        let applied = file.fix_consistency();
+        write(args.output(), &file.to_bytes())?;
+
+        if !args.json {
+            emit(&[format!("{} fix(es) applied", applied)])?;
+        }
+
+        return Ok(());
+    }
+
+    if !findings.is_empty() {
+        return Err(CliError::Failed(anyhow!(
+            "{} problem(s) found",
+            findings.len()
+        )));
+    }
+
+    Ok(())
+}
+
+fn diff_files(args: &Args) -> Result<(), CliError> {
+    args.expect(2)?;
+
+    let old = open(args.file())?;
+    let new = open(Path::new(&args.positional[1]))?;
+    let changes = diff(&old, &new);
+
+    if args.json {
+        emit(&[json::stringify_pretty(render_json(&changes), 2)])?;
+    } else if !changes.is_empty() {
+        emit(&[render_text(&changes)])?;
+    }
+
+    Ok(())
+}
+
+fn merge_files(args: &Args) -> Result<(), CliError> {
+    args.expect(3)?;
+
+    let Some(output) = &args.output else {
+        return Err(CliError::Usage(String::from("merge needs -o/--output")));
+    };
+
+    let base = open(args.file())?;
+    let ours = open(Path::new(&args.positional[1]))?;
+    let theirs = open(Path::new(&args.positional[2]))?;
+
+    let strategy = args.strategy.unwrap_or(MergeStrategy::Ours);
+    let result = merge(&base, &ours, &theirs, strategy);
+
+    let lines: Vec<String> = result
+        .conflicts
+        .iter()
+        .map(|c| {
+            let label = if c.label.is_empty() {
+                String::new()
+            } else {
+                format!(" ({})", c.label)
+            };
+
+            format!(
+                "conflict: {}{}: base {}, ours {}, theirs {} -> {}",
+                c.path, label, c.base, c.ours, c.theirs, c.resolved
+            )
+        })
+        .collect();
+
+    emit(&lines)?;
+
+    if !result.conflicts.is_empty() && args.strategy.is_none() {
+        return Err(CliError::Failed(anyhow!(
+            "{} conflict(s); choose a --strategy to resolve them",
+            result.conflicts.len()
+        )));
+    }
+
+    write(output, &result.merged.to_bytes())?;
+
+    Ok(())
+}
+
+fn run(raw: &[String]) -> Result<(), CliError> {
+    let Some((command, rest)) = raw.split_first() else {
+        return Err(CliError::Usage(String::from("no command given")));
+    };
+
+    let args = Args::parse(rest)?;
+
+    match command.as_str() {
+        "new" => new_file(&args),
+        "info" => info(&args),
+        "dump" => dump(&args),
+        "get" => get(&args),
+        "set" => set(&args),
+        "copy-slot" => copy_slot(&args),
+        "swap-slot" => swap_slot(&args),
+        "clear-slot" => clear_slot(&args),
+        "promote-slot" => promote_slot(&args),
+        "new-game" => new_game(&args),
+        "sync-flags" => sync_flags(&args),
+        "presets" => list_presets(&args),
+        "preset" => apply_preset(&args),
+        "fix-crc" => fix_crc(&args),
+        "export" => export(&args),
+        "import" => import(&args),
+        "verify" => verify(&args),
+        "check" => check(&args),
+        "diff" => diff_files(&args),
+        "merge" => merge_files(&args),
+
+        "schema" => {
+            args.expect(0)?;
+            emit(&[JSON_SCHEMA.trim_end().to_string()])?;
+            Ok(())
+        }
+
+        "help" | "-h" | "--help" => {
+            emit(&[USAGE.to_string()])?;
+            Ok(())
+        }
+
+        other => Err(CliError::Usage(format!("unknown command {:?}", other))),
+    }
+}
+
+fn main() -> ExitCode {
+    let raw: Vec<String> = env::args().skip(1).collect();
+
+    match run(&raw) {
+        Ok(()) => ExitCode::SUCCESS,
+
+        Err(CliError::Usage(message)) => {
+            eprintln!("error: {}\n\n{}", message, USAGE);
+            ExitCode::from(2)
+        }
+
+        Err(CliError::Failed(e)) => {
+            eprintln!("error: {:#}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
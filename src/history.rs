@@ -0,0 +1,156 @@
+use eframe::egui;
+
+use crate::savefile::{
+    savediff::{diff, render_text},
+    SaveFile,
+};
+
+// every entry is a full copy of the file, which is only about 15 KiB
+const HISTORY_LIMIT: usize = 200;
+
+struct HistoryEntry {
+    label: String,
+    file: SaveFile,
+}
+
+/// Snapshots of the file after each edit, oldest first.
+pub struct History {
+    entries: Vec<HistoryEntry>,
+    position: usize,
+    revision: u64,
+}
+
+/// Describes the edit between two snapshots, e.g. `slot[0].player_lives[0]: 5 -> 99`.
+fn describe(old: &SaveFile, new: &SaveFile) -> String {
+    let changes = diff(old, new);
+
+    match changes.len() {
+        0 => String::from("No changes"),
+        1 => render_text(&changes),
+        n => format!("{} (and {} more)", render_text(&changes[..1]), n - 1),
+    }
+}
+
+impl History {
+    pub fn new(file: &SaveFile) -> Self {
+        let mut history = Self {
+            entries: Vec::new(),
+            position: 0,
+            revision: 0,
+        };
+
+        history.reset(file, "Blank file");
+
+        history
+    }
+
+    /// Forgets every edit and starts over from `file`.
+    pub fn reset(&mut self, file: &SaveFile, label: &str) {
+        self.entries = vec![HistoryEntry {
+            label: label.to_string(),
+            file: file.clone(),
+        }];
+        self.position = 0;
+        self.revision += 1;
+    }
+
+    /// The state the editor should be in.
+    pub fn current(&self) -> &SaveFile {
+        &self.entries[self.position].file
+    }
+
+    /// Changes whenever [`History::current`] does, so that anything worked out from the
+    /// current state can be kept until then.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Adds `file` as a new step if it differs from the current one, dropping anything
+    /// that was undone. Without a label the step is named after what changed.
+    pub fn record(&mut self, file: &SaveFile, label: Option<String>) {
+        if file == self.current() {
+            return;
+        }
+
+        let label = label.unwrap_or_else(|| describe(self.current(), file));
+
+        self.entries.truncate(self.position + 1);
+        self.entries.push(HistoryEntry {
+            label,
+            file: file.clone(),
+        });
+
+        if self.entries.len() > HISTORY_LIMIT {
+            self.entries.remove(0);
+        }
+
+        self.position = self.entries.len() - 1;
+        self.revision += 1;
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.position > 0
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.position + 1 < self.entries.len()
+    }
+
+    pub fn undo(&mut self) -> Option<&SaveFile> {
+        if !self.can_undo() {
+            return None;
+        }
+
+        self.position -= 1;
+        self.revision += 1;
+
+        Some(self.current())
+    }
+
+    pub fn redo(&mut self) -> Option<&SaveFile> {
+        if !self.can_redo() {
+            return None;
+        }
+
+        self.position += 1;
+        self.revision += 1;
+
+        Some(self.current())
+    }
+
+    /// Lists every step, newest last. Returns the step that was clicked.
+    pub fn show_ui(&self, ui: &mut egui::Ui) -> Option<usize> {
+        let mut clicked = None;
+
+        egui::ScrollArea::vertical()
+            .auto_shrink([false, true])
+            .stick_to_bottom(true)
+            .show(ui, |ui| {
+                for (i, entry) in self.entries.iter().enumerate() {
+                    let text = if i > self.position {
+                        egui::RichText::new(&entry.label).weak()
+                    } else {
+                        egui::RichText::new(&entry.label)
+                    };
+
+                    if ui.selectable_label(i == self.position, text).clicked() {
+                        clicked = Some(i);
+                    }
+                }
+            });
+
+        clicked
+    }
+
+    /// Moves to any step in the list, as if undoing or redoing up to it.
+    pub fn jump(&mut self, index: usize) -> Option<&SaveFile> {
+        if index >= self.entries.len() || index == self.position {
+            return None;
+        }
+
+        self.position = index;
+        self.revision += 1;
+
+        Some(self.current())
+    }
+}
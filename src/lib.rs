@@ -0,0 +1,21 @@
+//! Reading and writing New Super Mario Bros. Wii save files.
+//!
+//! The save format lives in [`savefile`] and has no GUI dependencies.
+//! The editor front end is only built with the `gui` feature, which is on by default.
+
+pub mod backup;
+pub mod savefile;
+
+#[cfg(feature = "gui")]
+mod history;
+#[cfg(feature = "gui")]
+mod notifications;
+#[cfg(feature = "gui")]
+mod penguin;
+#[cfg(feature = "gui")]
+mod settings;
+#[cfg(feature = "gui")]
+mod views;
+
+#[cfg(feature = "gui")]
+pub use penguin::PenguinApp;
@@ -0,0 +1,219 @@
+use eframe::egui;
+use std::time::Instant;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Severity {
+    Success,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    fn label(self) -> &'static str {
+        match self {
+            Severity::Success => "Success",
+            Severity::Warning => "Warning",
+            Severity::Error => "Error",
+        }
+    }
+
+    fn color(self, visuals: &egui::Visuals) -> egui::Color32 {
+        match self {
+            Severity::Success => egui::Color32::from_rgb(0x4C, 0xAF, 0x50),
+            Severity::Warning => visuals.warn_fg_color,
+            Severity::Error => visuals.error_fg_color,
+        }
+    }
+
+    /// How long a toast stays up, in seconds. Errors stay until dismissed.
+    fn duration(self) -> Option<f32> {
+        match self {
+            Severity::Success => Some(4.0),
+            Severity::Warning => Some(8.0),
+            Severity::Error => None,
+        }
+    }
+}
+
+struct LogEntry {
+    severity: Severity,
+    /// Seconds since the session started.
+    time: u64,
+    message: String,
+}
+
+struct Toast {
+    entry: usize,
+    shown_at: Option<f64>,
+}
+
+/// Short-lived toasts for the outcome of file operations, plus a log of every
+/// message posted in this session.
+pub struct Notifications {
+    start: Instant,
+    log: Vec<LogEntry>,
+    toasts: Vec<Toast>,
+    pub show_log: bool,
+}
+
+impl Notifications {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            log: Vec::new(),
+            toasts: Vec::new(),
+            show_log: false,
+        }
+    }
+
+    pub fn post(&mut self, severity: Severity, message: impl Into<String>) {
+        self.log.push(LogEntry {
+            severity,
+            time: self.start.elapsed().as_secs(),
+            message: message.into(),
+        });
+
+        self.toasts.push(Toast {
+            entry: self.log.len() - 1,
+            shown_at: None,
+        });
+    }
+
+    pub fn success(&mut self, message: impl Into<String>) {
+        self.post(Severity::Success, message);
+    }
+
+    pub fn warning(&mut self, message: impl Into<String>) {
+        self.post(Severity::Warning, message);
+    }
+
+    pub fn error(&mut self, message: impl Into<String>) {
+        self.post(Severity::Error, message);
+    }
+
+    /// Draws the toasts in the bottom right corner and the log window if it's open.
+    pub fn show(&mut self, ctx: &egui::Context) {
+        self.show_toasts(ctx);
+
+        if self.show_log {
+            self.show_log(ctx);
+        }
+    }
+
+    fn show_toasts(&mut self, ctx: &egui::Context) {
+        let now = ctx.input(|i| i.time);
+        let log = &self.log;
+        let mut open_log = false;
+
+        self.toasts.retain_mut(|toast| {
+            let shown_at = *toast.shown_at.get_or_insert(now);
+
+            match log[toast.entry].severity.duration() {
+                Some(duration) => now - shown_at < duration as f64,
+                None => true,
+            }
+        });
+
+        if self.toasts.is_empty() {
+            return;
+        }
+
+        let mut dismissed = Vec::new();
+
+        egui::Area::new(egui::Id::new("notifications"))
+            .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-8.0, -8.0))
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                ui.set_max_width(360.0);
+
+                for (i, toast) in self.toasts.iter().enumerate() {
+                    let entry = &log[toast.entry];
+                    let color = entry.severity.color(ui.visuals());
+
+                    egui::Frame::popup(ui.style())
+                        .stroke(egui::Stroke::new(1.0, color))
+                        .show(ui, |ui| {
+                            ui.horizontal(|ui| {
+                                ui.colored_label(color, entry.severity.label());
+
+                                ui.with_layout(
+                                    egui::Layout::right_to_left(egui::Align::Center),
+                                    |ui| {
+                                        if ui.small_button("✖").clicked() {
+                                            dismissed.push(i);
+                                        }
+
+                                        if ui.small_button("Log").clicked() {
+                                            open_log = true;
+                                        }
+                                    },
+                                );
+                            });
+
+                            ui.label(&entry.message);
+                        });
+                }
+            });
+
+        for i in dismissed.into_iter().rev() {
+            self.toasts.remove(i);
+        }
+
+        if open_log {
+            self.show_log = true;
+        }
+
+        // keep repainting so timed toasts disappear without further input
+        ctx.request_repaint_after(std::time::Duration::from_millis(250));
+    }
+
+    fn show_log(&mut self, ctx: &egui::Context) {
+        let mut clear = false;
+
+        egui::Window::new("Log")
+            .open(&mut self.show_log)
+            .default_size(egui::vec2(480.0, 300.0))
+            .show(ctx, |ui| {
+                if ui
+                    .add_enabled(!self.log.is_empty(), egui::Button::new("Clear"))
+                    .clicked()
+                {
+                    clear = true;
+                }
+
+                ui.separator();
+
+                if self.log.is_empty() {
+                    ui.label("Nothing has happened yet.");
+                    return;
+                }
+
+                egui::ScrollArea::vertical()
+                    .auto_shrink([false, true])
+                    .stick_to_bottom(true)
+                    .show(ui, |ui| {
+                        egui::Grid::new("log").striped(true).show(ui, |ui| {
+                            for entry in &self.log {
+                                ui.monospace(format!(
+                                    "{:02}:{:02}:{:02}",
+                                    entry.time / 3600,
+                                    entry.time % 3600 / 60,
+                                    entry.time % 60
+                                ));
+                                ui.colored_label(
+                                    entry.severity.color(ui.visuals()),
+                                    entry.severity.label(),
+                                );
+                                ui.label(&entry.message);
+                                ui.end_row();
+                            }
+                        });
+                    });
+            });
+
+        if clear {
+            self.log.clear();
+            self.toasts.clear();
+        }
+    }
+}
@@ -1,11 +1,23 @@
-use crate::savefile::SaveFile;
+use crate::backup::{backup_dir, create_backup, list_backups, write_atomic, Backup};
+use crate::history::History;
+use crate::notifications::Notifications;
+use crate::savefile::{
+    checksum::{repair_checksums, CrcRegion, CrcReport},
+    constants::{SaveFileRegion, NORMAL_SLOT_COUNT, SAVE_SLOT_COUNT},
+    get_slot_string,
+    presets::{builtin_presets, load_presets, Preset},
+    saveconsistency::Finding,
+    savediff::field_label,
+    savevalidate::{Problem, Severity},
+    SaveFile,
+};
 use crate::settings::*;
 use anyhow::Result;
 use eframe::{self, egui, NativeOptions};
-use egui::{Button, IconData};
+use egui::{Button, IconData, Key, KeyboardShortcut, Modifiers, ViewportCommand};
 use std::env;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use crate::views::{header_view::*, slot_view::*, PenguinView};
@@ -16,41 +28,126 @@ pub struct PenguinApp {
     show_settings: bool,
     file: SaveFile,
     file_open: bool,
+    // created with "New" and not saved anywhere yet
+    file_is_new: bool,
+    new_file_region: Option<SaveFileRegion>,
     current_view: PenguinView,
     header_view: HeaderView,
     current_slot_index: usize,
+    target_slot_index: usize,
     slot_view: SlotView,
     first_frame_update: bool,
+    notifications: Notifications,
+    // the report and the file it was made for, which isn't the open file after restoring a backup
+    crc_report: Option<(PathBuf, CrcReport)>,
+    history: History,
+    history_label: Option<String>,
+    show_history: bool,
+    show_problems: bool,
+    backups: Option<Vec<Backup>>,
+    saved_bytes: Vec<u8>,
+    // the history revision `dirty` and `problems` were worked out for
+    checked_revision: Option<u64>,
+    dirty: bool,
+    problems: Vec<Finding>,
+    pending_action: Option<PendingAction>,
+    allow_close: bool,
+    window_title: String,
+    presets: Vec<Preset>,
+    preset_index: usize,
+    // the presets file `presets` was last loaded from
+    presets_file: Option<PathBuf>,
 }
 
-fn get_slot_string(index: usize) -> String {
-    match index {
-        0..=2 => String::from("Save Slot ") + &format!("{}", index + 1),
-        3..=5 => String::from("Quick Slot ") + &format!("{}", index - 2),
-        _ => String::from("error"),
-    }
+/// Something that would throw away unsaved changes, held until the user confirms it.
+#[derive(Clone, Copy, PartialEq)]
+enum PendingAction {
+    New,
+    Open,
+    Refresh,
+    Close,
 }
 
+const UNDO_SHORTCUT: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::Z);
+const REDO_SHORTCUT: KeyboardShortcut =
+    KeyboardShortcut::new(Modifiers::COMMAND.plus(Modifiers::SHIFT), Key::Z);
+
 impl PenguinApp {
     fn new() -> Self {
+        let file = SaveFile::blank();
+
         let mut app = Self {
             file_path: env::current_dir().unwrap(),
             settings: PenguinSettings::default(),
             show_settings: false,
-            file: SaveFile::blank(),
+            history: History::new(&file),
+            history_label: None,
+            show_history: false,
+            show_problems: false,
+            backups: None,
+            saved_bytes: file.to_bytes(),
+            checked_revision: None,
+            dirty: false,
+            problems: Vec::new(),
+            pending_action: None,
+            allow_close: false,
+            window_title: String::from("Penguin"),
+            presets: builtin_presets(),
+            preset_index: 0,
+            presets_file: None,
+            file,
             file_open: false,
+            file_is_new: false,
+            new_file_region: None,
             current_view: PenguinView::Header,
             header_view: HeaderView::new(),
             current_slot_index: 0,
+            target_slot_index: 1,
             slot_view: SlotView::new(),
             first_frame_update: true,
+            notifications: Notifications::new(),
+            crc_report: None,
         };
 
-        let _ = app.settings.load();
+        if let Err(e) = app.settings.load() {
+            app.notifications.warning(format!(
+                "Failed to load {}, using the default settings: {}",
+                SETTINGS_PATH, e
+            ));
+        }
+
+        app.reload_presets();
 
         app
     }
 
+    /// Loads the built-in presets and the ones in the presets file from the settings.
+    fn reload_presets(&mut self) {
+        self.presets = builtin_presets();
+        self.presets_file = self.settings.presets_file().map(PathBuf::from);
+
+        let Some(path) = &self.presets_file else {
+            return;
+        };
+
+        let loaded = fs::read_to_string(path)
+            .map_err(anyhow::Error::from)
+            .and_then(|input| Ok(load_presets(&input)?));
+
+        match loaded {
+            Ok(presets) => self.presets.extend(presets),
+            Err(e) => self.notifications.error(format!(
+                "Failed to load presets from {}: {}",
+                path.display(),
+                e
+            )),
+        }
+
+        if self.preset_index >= self.presets.len() {
+            self.preset_index = 0;
+        }
+    }
+
     /// Runs the application
     pub fn run() -> Result<(), eframe::Error> {
         let mut options = NativeOptions::default();
@@ -84,25 +181,471 @@ impl PenguinApp {
             .pick_file();
 
         if let Some(p) = path {
-            self.file_path = p;
+            match SaveFile::from_path_checked(&p) {
+                Ok((f, report)) => {
+                    self.notifications
+                        .success(format!("Opened {}", p.display()));
+                    self.set_crc_report(&p, report);
+                    self.file_path = p;
+                    self.file_open = true;
+                    self.file_is_new = false;
+                    self.file = f;
+                    self.mark_saved(self.file.to_bytes());
+                    self.history.reset(&self.file, "Opened file");
+                }
 
-            if let Some(f) = SaveFile::from_path(&self.file_path) {
-                self.file_open = true;
-                self.file = f;
+                Err(e) => {
+                    self.notifications
+                        .error(format!("Failed to open {}: {}", p.display(), e));
+                }
             }
         }
     }
 
     fn reopen(&mut self) {
-        if let Some(f) = SaveFile::from_path(&self.file_path) {
-            self.file = f
+        match SaveFile::from_path_checked(&self.file_path) {
+            Ok((f, report)) => {
+                self.file = f;
+                self.mark_saved(self.file.to_bytes());
+                self.history.reset(&self.file, "Reloaded file");
+                self.set_crc_report(&self.file_path.clone(), report);
+                self.notifications
+                    .success(format!("Reloaded {}", self.file_path.display()));
+            }
+            Err(e) => {
+                self.notifications.error(format!(
+                    "Failed to reload {}: {}",
+                    self.file_path.display(),
+                    e
+                ));
+            }
+        }
+    }
+
+    fn set_crc_report(&mut self, path: &Path, report: CrcReport) {
+        self.crc_report = if report.is_valid() {
+            None
+        } else {
+            self.notifications
+                .warning("Some parts of the file do not match their checksums.");
+
+            Some((path.to_path_buf(), report))
+        };
+    }
+
+    /// Rewrites the checksums of a file on disk, backing it up first like saving does.
+    /// Returns where the backup went, if one was made.
+    fn repair_file_checksums(&self, path: &Path) -> Result<Option<PathBuf>> {
+        let mut bytes = fs::read(path)?;
+        repair_checksums(&mut bytes)?;
+
+        let dir = backup_dir(path, self.settings.backup_directory());
+        let backup = create_backup(path, &dir, self.settings.backup_count())?;
+
+        write_atomic(path, &bytes)?;
+
+        Ok(backup)
+    }
+
+    fn show_crc_report(&mut self, ctx: &egui::Context) {
+        let Some((path, report)) = self.crc_report.clone() else {
+            return;
+        };
+
+        let mut dismissed = false;
+
+        egui::Window::new("Checksum mismatch")
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "Some parts of {} do not match their checksums.",
+                    path.display()
+                ));
+                ui.add_space(3.0);
+
+                egui::Grid::new("crc_report").striped(true).show(ui, |ui| {
+                    ui.strong("Region");
+                    ui.strong("Stored");
+                    ui.strong("Expected");
+                    ui.strong("Status");
+                    ui.end_row();
+
+                    for check in report.checks() {
+                        ui.label(match check.region {
+                            CrcRegion::Header => String::from("Header"),
+                            CrcRegion::Slot(i) => get_slot_string(i),
+                        });
+                        ui.monospace(format!("{:08X}", check.stored));
+                        ui.monospace(format!("{:08X}", check.expected));
+                        ui.label(if check.is_valid() {
+                            "Valid"
+                        } else {
+                            "Mismatch"
+                        });
+                        ui.end_row();
+                    }
+                });
+
+                ui.add_space(3.0);
+
+                ui.horizontal(|ui| {
+                    if ui
+                        .button("Repair checksums")
+                        .on_hover_text(
+                            "Rewrites the checksums in the file, keeping the data as-is.",
+                        )
+                        .clicked()
+                    {
+                        match self.repair_file_checksums(&path) {
+                            Ok(backup) => self.notifications.success(match backup {
+                                Some(b) => format!(
+                                    "Repaired the checksums in {} (previous version backed up to {})",
+                                    path.display(),
+                                    b.display()
+                                ),
+                                None => format!("Repaired the checksums in {}", path.display()),
+                            }),
+
+                            Err(e) => self.notifications.error(format!(
+                                "Failed to repair {}: {}",
+                                path.display(),
+                                e
+                            )),
+                        }
+
+                        dismissed = true;
+                    }
+
+                    if ui
+                        .button("Ignore")
+                        .on_hover_text("Checksums will be rewritten when the file is saved.")
+                        .clicked()
+                    {
+                        dismissed = true;
+                    }
+
+                    if ui
+                        .add_enabled(
+                            !report.corrupt_slots().is_empty(),
+                            Button::new("Reset corrupt slots"),
+                        )
+                        .on_hover_text(
+                            "Empties every slot with a bad checksum, like the game does.",
+                        )
+                        .clicked()
+                    {
+                        self.file.reset_corrupt_slots(&report);
+                        self.history_label = Some(String::from("Reset corrupt slots"));
+                        dismissed = true;
+                    }
+                });
+            });
+
+        if dismissed {
+            self.crc_report = None;
+        }
+    }
+
+    fn show_slot_operations(&mut self, ui: &mut egui::Ui) {
+        let current = self.current_slot_index;
+
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_salt("target_slot")
+                .selected_text(get_slot_string(self.target_slot_index))
+                .show_ui(ui, |ui| {
+                    for i in (0..SAVE_SLOT_COUNT).filter(|i| *i != current) {
+                        ui.selectable_value(&mut self.target_slot_index, i, get_slot_string(i));
+                    }
+                });
+
+            let target = self.target_slot_index;
+            let valid_target = target != current;
+
+            if ui
+                .add_enabled(valid_target, Button::new("Copy to"))
+                .on_hover_text("Overwrites the target slot with this one.")
+                .clicked()
+            {
+                let _ = self.file.copy_slot(current, target);
+                self.history_label = Some(format!(
+                    "Copied {} to {}",
+                    get_slot_string(current),
+                    get_slot_string(target)
+                ));
+            }
+
+            if ui
+                .add_enabled(valid_target, Button::new("Swap with"))
+                .clicked()
+            {
+                let _ = self.file.swap_slots(current, target);
+                self.history_label = Some(format!(
+                    "Swapped {} with {}",
+                    get_slot_string(current),
+                    get_slot_string(target)
+                ));
+            }
+
+            ui.separator();
+
+            if ui
+                .button("Clear")
+                .on_hover_text("Resets this slot to the empty state.")
+                .clicked()
+            {
+                let _ = self.file.clear_slot(current);
+                self.history_label = Some(format!("Cleared {}", get_slot_string(current)));
+            }
+
+            if ui
+                .button("Reset to new game")
+                .on_hover_text(
+                    "Resets this slot to the state the game writes when a file is started.",
+                )
+                .clicked()
+            {
+                let _ = self.file.start_new_game(current);
+                self.history_label =
+                    Some(format!("Reset {} to a new game", get_slot_string(current)));
+            }
+
+            if current >= NORMAL_SLOT_COUNT
+                && ui
+                    .add_enabled(
+                        !self.file.save_slots[current].is_empty(),
+                        Button::new("Promote to save slot"),
+                    )
+                    .on_hover_text(format!(
+                        "Moves this quick save into {}, like loading it in the game does.",
+                        get_slot_string(current - NORMAL_SLOT_COUNT)
+                    ))
+                    .clicked()
+            {
+                match self.file.promote_quick_save(current) {
+                    Ok(()) => {
+                        self.history_label = Some(format!("Promoted {}", get_slot_string(current)));
+                        self.current_slot_index = current - NORMAL_SLOT_COUNT;
+                    }
+                    Err(e) => self.notifications.error(format!(
+                        "Failed to promote {}: {}",
+                        get_slot_string(current),
+                        e
+                    )),
+                }
+            }
+        });
+
+        ui.horizontal(|ui| {
+            let selected = &self.presets[self.preset_index];
+
+            egui::ComboBox::from_id_salt("preset")
+                .selected_text(&selected.name)
+                .show_ui(ui, |ui| {
+                    for (i, preset) in self.presets.iter().enumerate() {
+                        ui.selectable_value(&mut self.preset_index, i, &preset.name)
+                            .on_hover_text(&preset.description);
+                    }
+                });
+
+            if ui
+                .button("Apply preset")
+                .on_hover_text(format!(
+                    "{}\n\nReplaces the stages, unlocked worlds, completion flags, hint movies \
+                     and current world of this slot.",
+                    selected.description
+                ))
+                .clicked()
+            {
+                let preset = selected.clone();
+
+                match self.file.apply_preset(current, &preset) {
+                    Ok(()) => {
+                        self.history_label = Some(format!(
+                            "Applied \"{}\" to {}",
+                            preset.name,
+                            get_slot_string(current)
+                        ))
+                    }
+                    Err(e) => self.notifications.error(format!(
+                        "Failed to apply \"{}\" to {}: {}",
+                        preset.name,
+                        get_slot_string(current),
+                        e
+                    )),
+                }
+            }
+        });
+
+        if self.target_slot_index == self.current_slot_index {
+            self.target_slot_index = (self.current_slot_index + 1) % SAVE_SLOT_COUNT;
+        }
+    }
+
+    /// Records whatever changed since the last step. Edits are held back while something
+    /// is being dragged or typed into, so that each of those ends up as a single step.
+    fn record_history(&mut self, ctx: &egui::Context) {
+        if ctx.dragged_id().is_some() || ctx.wants_keyboard_input() {
+            return;
+        }
+
+        // only after an edit, so opening a file doesn't change it
+        if self.settings.sync_completion_flags() && self.file != *self.history.current() {
+            self.file.sync_completion_flags();
+        }
+
+        self.history.record(&self.file, self.history_label.take());
+    }
+
+    fn undo(&mut self) {
+        self.history.record(&self.file, self.history_label.take());
+
+        if let Some(file) = self.history.undo() {
+            self.file = file.clone();
+        }
+    }
+
+    fn redo(&mut self) {
+        self.history.record(&self.file, self.history_label.take());
+
+        if let Some(file) = self.history.redo() {
+            self.file = file.clone();
         }
     }
 
-    fn try_save(&self, save_as: bool) {
-        let mut empty = false;
+    fn show_history(&mut self, ctx: &egui::Context) {
+        let mut clicked = None;
+
+        egui::Window::new("History")
+            .open(&mut self.show_history)
+            .default_height(300.0)
+            .show(ctx, |ui| {
+                clicked = self.history.show_ui(ui);
+            });
+
+        if let Some(index) = clicked {
+            self.history.record(&self.file, self.history_label.take());
+
+            if let Some(file) = self.history.jump(index) {
+                self.file = file.clone();
+            }
+        }
+    }
+
+    /// Shows the view, slot and picker that hold a problem's field.
+    fn jump_to(&mut self, problem: &Problem) {
+        match problem.slot {
+            None => self.current_view = PenguinView::Header,
+
+            Some(i) => {
+                self.current_view = PenguinView::SaveSlot;
+                self.current_slot_index = i;
+                self.slot_view.focus(problem.field, &problem.index);
+            }
+        }
+    }
+
+    fn show_problems(&mut self, ctx: &egui::Context, problems: &[Finding]) {
+        let mut clicked = None;
+        let mut fix = None;
+        let mut fix_all = false;
+
+        egui::Window::new("Problems")
+            .open(&mut self.show_problems)
+            .default_size(egui::vec2(520.0, 300.0))
+            .show(ctx, |ui| {
+                if problems.is_empty() {
+                    ui.label("No problems found.");
+                    return;
+                }
 
-        let path = if !save_as {
+                ui.horizontal(|ui| {
+                    ui.label(
+                        "Values the game would never write and progress it can't reach. \
+                         Click one to go to the field.",
+                    );
+
+                    let fixable = problems.iter().any(|f| f.fix.is_some());
+
+                    if ui.add_enabled(fixable, Button::new("Fix all")).clicked() {
+                        fix_all = true;
+                    }
+                });
+                ui.separator();
+
+                egui::ScrollArea::vertical()
+                    .auto_shrink([false, true])
+                    .show(ui, |ui| {
+                        egui::Grid::new("problems").striped(true).show(ui, |ui| {
+                            for (i, finding) in problems.iter().enumerate() {
+                                let problem = &finding.problem;
+                                let color = match problem.severity {
+                                    Severity::Warning => ui.visuals().warn_fg_color,
+                                    Severity::Error => ui.visuals().error_fg_color,
+                                };
+
+                                ui.colored_label(color, problem.severity.name());
+
+                                let label = field_label(problem.field, &problem.index);
+                                let location = match problem.slot {
+                                    Some(s) if label.is_empty() => get_slot_string(s),
+                                    Some(s) => format!("{}, {}", get_slot_string(s), label),
+                                    None => String::from("Header"),
+                                };
+
+                                if ui
+                                    .link(format!("{}: {}", location, problem.field))
+                                    .on_hover_text(problem.path())
+                                    .clicked()
+                                {
+                                    clicked = Some(i);
+                                }
+
+                                ui.label(&problem.message);
+
+                                match finding.fix {
+                                    Some(f) => {
+                                        if ui
+                                            .small_button("Fix")
+                                            .on_hover_text(f.description())
+                                            .clicked()
+                                        {
+                                            fix = Some(i);
+                                        }
+                                    }
+                                    None => {
+                                        ui.label("");
+                                    }
+                                }
+
+                                ui.end_row();
+                            }
+                        });
+                    });
+            });
+
+        if let Some(i) = clicked {
+            self.jump_to(&problems[i].problem);
+        }
+
+        if let Some(i) = fix {
+            if self.file.apply_fix(&problems[i]) {
+                self.history_label = problems[i].fix.map(|f| f.description());
+            }
+        }
+
+        if fix_all {
+            let applied = self.file.fix_consistency();
+
+            self.history_label = Some(String::from("Fix all problems"));
+            self.notifications
+                .success(format!("Applied {} fix(es)", applied));
+        }
+    }
+
+    /// Saves the file, returning whether it was written.
+    fn try_save(&mut self, save_as: bool) -> bool {
+        let path = if !save_as && !self.file_is_new {
             self.file_path.clone()
         } else {
             match rfd::FileDialog::new()
@@ -111,26 +654,335 @@ impl PenguinApp {
                 .save_file()
             {
                 Some(p) => p,
-                None => {
-                    empty = true;
-                    PathBuf::default()
-                }
+                None => return false,
+            }
+        };
+
+        let dir = backup_dir(&path, self.settings.backup_directory());
+
+        let backup = match create_backup(&path, &dir, self.settings.backup_count()) {
+            Ok(b) => b,
+
+            Err(e) => {
+                self.notifications.error(format!(
+                    "Failed to back up {} to {}, so it was not saved: {}",
+                    path.display(),
+                    dir.display(),
+                    e
+                ));
+
+                return false;
             }
         };
 
-        // save file
+        let bytes = self.file.to_bytes();
+
+        if let Err(e) = write_atomic(&path, &bytes) {
+            self.notifications
+                .error(format!("Failed to save {}: {}", path.display(), e));
+
+            return false;
+        }
+
+        self.notifications.success(match backup {
+            Some(b) => format!(
+                "Saved {} (previous version backed up to {})",
+                path.display(),
+                b.display()
+            ),
+            None => format!("Saved {}", path.display()),
+        });
 
-        if empty {
+        self.file_path = path;
+        self.file_is_new = false;
+        self.mark_saved(bytes);
+
+        true
+    }
+
+    fn new_file(&mut self, region: SaveFileRegion) {
+        self.file = SaveFile::new(region);
+        self.file_open = true;
+        self.file_is_new = true;
+        self.crc_report = None;
+        self.backups = None;
+        // nothing has been written yet, so a new file always counts as unsaved
+        self.mark_saved(Vec::new());
+        self.history.reset(&self.file, "New file");
+        self.notifications
+            .success(format!("Created a new save file for {}", region.name()));
+    }
+
+    fn show_new_file(&mut self, ctx: &egui::Context) {
+        let Some(mut region) = self.new_file_region else {
             return;
+        };
+
+        let mut create = false;
+        let mut cancel = false;
+
+        egui::Window::new("New save file")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+            .show(ctx, |ui| {
+                ui.label("Creates a save like the game does on first boot, with every slot empty.");
+                ui.add_space(3.0);
+
+                egui::ComboBox::from_label("Region")
+                    .selected_text(region.name())
+                    .show_ui(ui, |ui| {
+                        for r in SaveFileRegion::ALL {
+                            ui.selectable_value(&mut region, r, r.name());
+                        }
+                    });
+
+                ui.add_space(3.0);
+
+                ui.horizontal(|ui| {
+                    create = ui.button("Create").clicked();
+                    cancel = ui.button("Cancel").clicked();
+                });
+            });
+
+        self.new_file_region = if create || cancel { None } else { Some(region) };
+
+        if create {
+            self.new_file(region);
         }
+    }
 
-        match fs::exists(&path) {
-            Ok(_b) => match fs::write(&path, self.file.to_bytes()) {
-                Ok(_) => {}
-                Err(_e) => {}
-            },
+    fn display_path(&self) -> String {
+        if self.file_is_new {
+            String::from("Untitled")
+        } else {
+            self.file_path.display().to_string()
+        }
+    }
+
+    fn mark_saved(&mut self, bytes: Vec<u8>) {
+        self.saved_bytes = bytes;
+        self.checked_revision = None;
+    }
 
-            Err(_e) => {}
+    /// Works out again what depends on the file, once per change rather than every frame.
+    fn refresh_checks(&mut self) {
+        let revision = Some(self.history.revision());
+
+        if self.checked_revision == revision {
+            return;
+        }
+
+        self.checked_revision = revision;
+
+        // whether the file differs from what was last opened or saved
+        self.dirty = self.file_open && self.file.to_bytes() != self.saved_bytes;
+
+        // validation problems followed by progress the game can't reach; only the
+        // latter can have fixes
+        self.problems = self
+            .file
+            .validate()
+            .into_iter()
+            .map(|problem| Finding { problem, fix: None })
+            .collect();
+
+        self.problems.extend(self.file.check_consistency());
+    }
+
+    fn update_title(&mut self, ctx: &egui::Context, dirty: bool) {
+        let title = if !self.file_open {
+            String::from("Penguin")
+        } else {
+            let name = if self.file_is_new {
+                String::from("Untitled")
+            } else {
+                self.file_path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or_default()
+            };
+
+            format!("{}{} - Penguin", if dirty { "*" } else { "" }, name)
+        };
+
+        if title != self.window_title {
+            ctx.send_viewport_cmd(ViewportCommand::Title(title.clone()));
+            self.window_title = title;
+        }
+    }
+
+    /// Runs `action` straight away, or asks first if there are unsaved changes.
+    fn request(&mut self, ctx: &egui::Context, action: PendingAction, dirty: bool) {
+        if dirty {
+            self.pending_action = Some(action);
+        } else {
+            self.perform(ctx, action);
+        }
+    }
+
+    fn perform(&mut self, ctx: &egui::Context, action: PendingAction) {
+        match action {
+            PendingAction::New => self.new_file_region = Some(SaveFileRegion::NTSC),
+            PendingAction::Open => self.try_open(),
+            PendingAction::Refresh => self.reopen(),
+
+            PendingAction::Close => {
+                self.allow_close = true;
+                ctx.send_viewport_cmd(ViewportCommand::Close);
+            }
+        }
+    }
+
+    fn refresh_backups(&mut self) {
+        let dir = backup_dir(&self.file_path, self.settings.backup_directory());
+
+        match list_backups(&self.file_path, &dir) {
+            Ok(backups) => self.backups = Some(backups),
+
+            Err(e) => {
+                self.backups = None;
+                self.notifications.error(format!(
+                    "Failed to list backups in {}: {}",
+                    dir.display(),
+                    e
+                ));
+            }
+        }
+    }
+
+    fn show_unsaved_changes(&mut self, ctx: &egui::Context) {
+        let Some(action) = self.pending_action else {
+            return;
+        };
+
+        let mut choice = None;
+
+        egui::Window::new("Unsaved changes")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "{} has unsaved changes. {}",
+                    self.display_path(),
+                    match action {
+                        PendingAction::New => "Save them before creating a new file?",
+                        PendingAction::Open => "Save them before opening another file?",
+                        PendingAction::Refresh => "Save them before reloading the file?",
+                        PendingAction::Close => "Save them before quitting?",
+                    }
+                ));
+
+                ui.add_space(3.0);
+
+                ui.horizontal(|ui| {
+                    if ui.button("Save").clicked() {
+                        choice = Some(true);
+                    }
+
+                    if ui.button("Discard").clicked() {
+                        choice = Some(false);
+                    }
+
+                    if ui.button("Cancel").clicked() {
+                        self.pending_action = None;
+                    }
+                });
+            });
+
+        // a failed save keeps the dialog open so nothing is lost
+        let proceed = match choice {
+            Some(save) => !save || self.try_save(false),
+            None => false,
+        };
+
+        if proceed {
+            self.pending_action = None;
+            self.perform(ctx, action);
+        }
+    }
+
+    /// Loads a backup into the editor. It only replaces the file on disk once saved.
+    fn restore_backup(&mut self, backup: &Backup) {
+        match SaveFile::from_path_checked(&backup.path) {
+            Ok((f, report)) => {
+                self.file = f;
+                self.history_label = Some(format!("Restored backup from {}", backup.timestamp));
+                self.set_crc_report(&backup.path, report);
+                self.notifications.success(format!(
+                    "Restored the backup from {}. Save to keep it.",
+                    backup.timestamp
+                ));
+            }
+
+            Err(e) => {
+                self.notifications.error(format!(
+                    "Failed to restore {}: {}",
+                    backup.path.display(),
+                    e
+                ));
+            }
+        }
+    }
+
+    fn show_backups(&mut self, ctx: &egui::Context) {
+        let Some(backups) = &self.backups else {
+            return;
+        };
+
+        let mut open = true;
+        let mut restore = None;
+        let mut refresh = false;
+
+        egui::Window::new("Restore backup")
+            .open(&mut open)
+            .default_height(300.0)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "Backups of {}, newest first (times are UTC).",
+                    self.file_path.display()
+                ));
+
+                if ui.button("Refresh").clicked() {
+                    refresh = true;
+                }
+
+                ui.separator();
+
+                if backups.is_empty() {
+                    ui.label("No backups yet. One is made each time the file is saved.");
+                    return;
+                }
+
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    egui::Grid::new("backups").striped(true).show(ui, |ui| {
+                        for backup in backups {
+                            ui.label(&backup.timestamp);
+                            ui.label(format!("{} bytes", backup.size));
+
+                            if ui
+                                .button("Restore")
+                                .on_hover_text(backup.path.display().to_string())
+                                .clicked()
+                            {
+                                restore = Some(backup.clone());
+                            }
+
+                            ui.end_row();
+                        }
+                    });
+                });
+            });
+
+        if let Some(backup) = restore {
+            self.restore_backup(&backup);
+        }
+
+        if !open {
+            self.backups = None;
+        } else if refresh {
+            self.refresh_backups();
         }
     }
 }
@@ -143,10 +995,34 @@ impl eframe::App for PenguinApp {
             self.settings.update_theme(ctx);
         }
 
+        self.refresh_checks();
+        let dirty = self.dirty;
+
+        self.update_title(ctx, dirty);
+
+        if ctx.input(|i| i.viewport().close_requested()) && dirty && !self.allow_close {
+            ctx.send_viewport_cmd(ViewportCommand::CancelClose);
+            self.pending_action = Some(PendingAction::Close);
+        }
+
+        if self.file_open && !ctx.wants_keyboard_input() {
+            // checked first since the undo shortcut would also match with shift held
+            if ctx.input_mut(|i| i.consume_shortcut(&REDO_SHORTCUT)) {
+                self.redo();
+            } else if ctx.input_mut(|i| i.consume_shortcut(&UNDO_SHORTCUT)) {
+                self.undo();
+            }
+        }
+
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
             egui::menu::bar(ui, |ui| {
+                if ui.button("New").clicked() {
+                    self.request(ctx, PendingAction::New, dirty);
+                    ui.close_menu();
+                }
+
                 if ui.button("Open").clicked() {
-                    self.try_open();
+                    self.request(ctx, PendingAction::Open, dirty);
                     ui.close_menu();
                 }
 
@@ -167,19 +1043,96 @@ impl eframe::App for PenguinApp {
                 }
 
                 if ui
-                    .add_enabled(self.file_open, Button::new("Refresh"))
+                    .add_enabled(
+                        self.file_open && !self.file_is_new,
+                        Button::new("Restore backup"),
+                    )
+                    .clicked()
+                {
+                    if self.backups.is_some() {
+                        self.backups = None;
+                    } else {
+                        self.refresh_backups();
+                    }
+                }
+
+                if ui
+                    .add_enabled(self.file_open && !self.file_is_new, Button::new("Refresh"))
                     .on_hover_text(
                         "Reloads the save file. This can be used if the file was saved externally.",
                     )
                     .clicked()
                 {
-                    self.reopen();
+                    self.request(ctx, PendingAction::Refresh, dirty);
                     ui.close_menu();
                 }
 
+                ui.separator();
+
+                if ui
+                    .add_enabled(
+                        self.file_open && self.history.can_undo(),
+                        Button::new("Undo").shortcut_text(ctx.format_shortcut(&UNDO_SHORTCUT)),
+                    )
+                    .clicked()
+                {
+                    self.undo();
+                }
+
+                if ui
+                    .add_enabled(
+                        self.file_open && self.history.can_redo(),
+                        Button::new("Redo").shortcut_text(ctx.format_shortcut(&REDO_SHORTCUT)),
+                    )
+                    .clicked()
+                {
+                    self.redo();
+                }
+
+                let problems_text = if !self.file_open || self.problems.is_empty() {
+                    String::from("Problems")
+                } else {
+                    format!("Problems ({})", self.problems.len())
+                };
+
+                if ui
+                    .add_enabled(self.file_open, Button::new(problems_text))
+                    .clicked()
+                {
+                    self.show_problems = !self.show_problems;
+                }
+
+                if ui
+                    .add_enabled(self.file_open, Button::new("History"))
+                    .clicked()
+                {
+                    self.show_history = !self.show_history;
+                }
+
+                ui.separator();
+
                 if ui.button("Settings").clicked() {
                     self.show_settings = !self.show_settings;
                 }
+
+                if ui.button("Log").clicked() {
+                    self.notifications.show_log = !self.notifications.show_log;
+                }
+
+                if self.file_open {
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        if dirty {
+                            ui.label(egui::RichText::new("Unsaved changes").strong())
+                                .on_hover_text(
+                                    "The file differs from what was last opened or saved.",
+                                );
+                        } else {
+                            ui.weak("Saved");
+                        }
+
+                        ui.monospace(self.display_path());
+                    });
+                }
             });
         });
 
@@ -209,7 +1162,7 @@ impl eframe::App for PenguinApp {
                         egui::ComboBox::from_label("Selected slot")
                             .selected_text(get_slot_string(self.current_slot_index))
                             .show_ui(ui, |ui| {
-                                for i in 0..=5 {
+                                for i in 0..SAVE_SLOT_COUNT {
                                     ui.selectable_value(
                                         &mut self.current_slot_index,
                                         i,
@@ -218,6 +1171,12 @@ impl eframe::App for PenguinApp {
                                 }
                             });
 
+                        self.show_slot_operations(ui);
+
+                        ui.separator();
+
+                        self.slot_view
+                            .set_sync_completion_flags(self.settings.sync_completion_flags());
                         self.slot_view
                             .show_ui(ui, &mut self.file.save_slots[self.current_slot_index]);
                     }
@@ -225,10 +1184,48 @@ impl eframe::App for PenguinApp {
             }
 
             if self.show_settings {
+                let mut save_settings = false;
+
                 egui::Window::new("Settings").show(ui.ctx(), |ui| {
-                    self.settings.show_ui(ui);
+                    save_settings = self.settings.show_ui(ui);
                 });
+
+                if self.settings.presets_file() != self.presets_file.as_deref() {
+                    self.reload_presets();
+                }
+
+                if save_settings {
+                    match self.settings.save() {
+                        Ok(()) => self.notifications.success("Saved settings"),
+                        Err(e) => self.notifications.error(format!(
+                            "Failed to save settings to {}: {}",
+                            SETTINGS_PATH, e
+                        )),
+                    }
+                }
+            }
+
+            if self.file_open {
+                self.show_history(ui.ctx());
+
+                if self.show_problems {
+                    // put back afterwards; a fix changes the file, so they're worked out again
+                    let problems = std::mem::take(&mut self.problems);
+                    self.show_problems(ui.ctx(), &problems);
+                    self.problems = problems;
+                }
+                self.show_backups(ui.ctx());
             }
+
+            self.show_crc_report(ui.ctx());
+            self.show_unsaved_changes(ui.ctx());
+            self.show_new_file(ui.ctx());
+
+            self.notifications.show(ui.ctx());
         });
+
+        if self.file_open {
+            self.record_history(ctx);
+        }
     }
 }
@@ -1,15 +1,63 @@
 use std::fs;
-use std::path::PathBuf;
+use std::io::Read;
+use std::path::Path;
 
+pub mod checksum;
 pub mod constants;
+pub mod fields;
+pub mod presets;
+pub mod saveconsistency;
+pub mod savediff;
+pub mod saveerror;
 pub mod saveheader;
+pub mod savejson;
+pub mod savemerge;
 pub mod saveslot;
+pub mod savevalidate;
+pub mod slotops;
+pub mod stages;
+use checksum::CrcReport;
+use constants::{SaveFileRegion, SAVE_FILE_SIZE};
+use saveerror::SaveError;
 use saveheader::SaveHeader;
 use saveslot::SaveSlot;
 
+/// The name the game uses for a slot index.
+pub fn get_slot_string(index: usize) -> String {
+    match index {
+        0..=2 => String::from("Save Slot ") + &format!("{}", index + 1),
+        3..=5 => String::from("Quick Slot ") + &format!("{}", index - 2),
+        _ => String::from("error"),
+    }
+}
+
+/// A generic name for a stage index. The same index is used for the same kind of stage in every world.
+pub fn get_stage_name_string(stage_index: usize) -> String {
+    match stage_index {
+        0..=8 => format!("Stage {}", stage_index + 1),
+        19 => String::from("Coin Battle Stage"),
+        20 => String::from("Ghost House"),
+        21 | 22 => format!("Tower {}", stage_index - 20),
+        23 | 24 => format!("Castle {}", stage_index - 22),
+        25..=28 => format!("Toad House {}", stage_index - 24),
+        32..=34 => format!("Enemy Ambush {}", stage_index - 31),
+        35 => String::from("Cannon"),
+        37 => String::from("Airship"),
+        38 => String::from("\"Rescue\""),
+        39 => String::from("Title Screen"),
+        40 => String::from("Peach's Castle"),
+        41 => String::from("Staff Credits"),
+        _ => format!("(unused index {})", stage_index),
+    }
+}
+
+#[derive(Clone, PartialEq)]
 pub struct SaveFile {
     pub header: SaveHeader,
     pub save_slots: [SaveSlot; 6],
+
+    // anything after the last slot, kept so that it can be written back
+    trailing: Vec<u8>,
 }
 
 impl SaveFile {
@@ -24,36 +72,105 @@ impl SaveFile {
                 SaveSlot::blank(),
                 SaveSlot::blank(),
             ],
+            trailing: Vec::new(),
         }
     }
 
-    pub fn from_path(path: &PathBuf) -> Option<Self> {
-        let bytes = match fs::read(path) {
-            Ok(b) => b,
-            Err(_) => {
-                return None;
-            }
-        };
+    /// A save as the game creates it on first boot, with every slot empty.
+    pub fn new(region: SaveFileRegion) -> Self {
+        let mut file = Self::blank();
+
+        file.header.region = region;
+        file.save_slots = std::array::from_fn(|_| SaveSlot::empty());
+
+        file
+    }
+
+    pub fn from_path(path: &Path) -> Result<Self, SaveError> {
+        let mut file = fs::File::open(path)?;
+
+        Self::from_reader(&mut file)
+    }
+
+    /// Like `from_path`, but also verifies the checksums of the header and each slot.
+    pub fn from_path_checked(path: &Path) -> Result<(Self, CrcReport), SaveError> {
+        let bytes = fs::read(path)?;
+
+        Self::from_bytes_checked(&bytes)
+    }
+
+    pub fn from_reader<R: Read>(reader: &mut R) -> Result<Self, SaveError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+
+        Self::from_bytes(&bytes)
+    }
 
-        let magic = &bytes[..3];
+    // check the magic first so foreign files aren't reported as merely truncated
+    fn check_magic_and_size(bytes: &[u8]) -> Result<(), SaveError> {
+        if bytes.len() >= 3 && &bytes[..3] != b"SMN" {
+            return Err(SaveError::BadMagic {
+                found: [bytes[0], bytes[1], bytes[2]],
+            });
+        }
 
-        // validate magic
-        if magic != b"SMN" {
-            return None;
+        if bytes.len() < SAVE_FILE_SIZE {
+            return Err(SaveError::TooShort {
+                expected: SAVE_FILE_SIZE,
+                actual: bytes.len(),
+            });
         }
 
-        let header = SaveHeader::from_bytes(&bytes);
+        Ok(())
+    }
+
+    // slots in `corrupt` that can't be parsed are replaced with empty ones, like the game does
+    fn parse(bytes: &[u8], corrupt: &[usize]) -> Result<Self, SaveError> {
+        Self::check_magic_and_size(bytes)?;
+
+        let header = SaveHeader::from_bytes(bytes)?;
 
-        let save_slots: [SaveSlot; 6] = [
-            SaveSlot::from_bytes(&bytes, 0),
-            SaveSlot::from_bytes(&bytes, 1),
-            SaveSlot::from_bytes(&bytes, 2),
-            SaveSlot::from_bytes(&bytes, 3),
-            SaveSlot::from_bytes(&bytes, 4),
-            SaveSlot::from_bytes(&bytes, 5),
-        ];
+        let slot = |index: usize| match SaveSlot::from_bytes(bytes, index) {
+            Err(_) if corrupt.contains(&index) => Ok(SaveSlot::empty()),
+            result => result,
+        };
+
+        let save_slots: [SaveSlot; 6] =
+            [slot(0)?, slot(1)?, slot(2)?, slot(3)?, slot(4)?, slot(5)?];
 
-        Some(Self { header, save_slots })
+        Ok(Self {
+            header,
+            save_slots,
+            trailing: bytes[SAVE_FILE_SIZE..].to_vec(),
+        })
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SaveError> {
+        Self::parse(bytes, &[])
+    }
+
+    /// Parses a save file and verifies its checksums.
+    /// A mismatch is not treated as an error; the caller decides what to do with the report.
+    /// A slot that fails its checksum and can't be parsed at all is loaded as an empty slot.
+    pub fn from_bytes_checked(bytes: &[u8]) -> Result<(Self, CrcReport), SaveError> {
+        Self::check_magic_and_size(bytes)?;
+
+        let report = CrcReport::from_bytes(bytes)?;
+        let file = Self::parse(bytes, &report.corrupt_slots())?;
+
+        Ok((file, report))
+    }
+
+    /// Resets every slot with a bad checksum to an empty slot, like the game does.
+    /// Returns the indices of the slots that were reset.
+    pub fn reset_corrupt_slots(&mut self, report: &CrcReport) -> Vec<usize> {
+        let corrupt = report.corrupt_slots();
+
+        for &i in corrupt.iter() {
+            self.save_slots[i] = SaveSlot::empty();
+        }
+
+        corrupt
     }
 
     pub fn to_bytes(&self) -> Vec<u8> {
@@ -64,6 +181,8 @@ impl SaveFile {
             out.append(&mut slot.to_bytes());
         }
 
+        out.extend_from_slice(&self.trailing);
+
         out
     }
 }
@@ -0,0 +1,122 @@
+use byteorder::{BigEndian, ByteOrder};
+use crc32fast as crc32;
+
+use crate::savefile::constants::*;
+use crate::savefile::saveerror::SaveError;
+
+pub const HEADER_CRC_OFFSET: usize = 0x69C;
+pub const SLOT_CRC_OFFSET: usize = 0x97C;
+
+/// A region of the save file that is covered by its own checksum.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum CrcRegion {
+    Header,
+    Slot(usize),
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct CrcCheck {
+    pub region: CrcRegion,
+    /// The checksum calculated from the data.
+    pub expected: u32,
+    /// The checksum found in the file.
+    pub stored: u32,
+}
+
+impl CrcCheck {
+    pub fn is_valid(&self) -> bool {
+        self.expected == self.stored
+    }
+}
+
+/// The result of checking every checksum in a save file.
+#[derive(Clone, Copy, Debug)]
+pub struct CrcReport {
+    pub header: CrcCheck,
+    pub slots: [CrcCheck; SAVE_SLOT_COUNT],
+}
+
+/// Calculates the header checksum. The magic is not included.
+pub fn header_crc(header: &[u8]) -> u32 {
+    crc32::hash(&header[4..HEADER_CRC_OFFSET])
+}
+
+/// Calculates the checksum of a single save slot.
+pub fn slot_crc(slot: &[u8]) -> u32 {
+    crc32::hash(&slot[..SLOT_CRC_OFFSET])
+}
+
+fn slot_range(index: usize) -> std::ops::Range<usize> {
+    let start = HEADER_SIZE + (SAVE_SLOT_SIZE * index);
+
+    start..start + SAVE_SLOT_SIZE
+}
+
+impl CrcReport {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SaveError> {
+        if bytes.len() < SAVE_FILE_SIZE {
+            return Err(SaveError::TooShort {
+                expected: SAVE_FILE_SIZE,
+                actual: bytes.len(),
+            });
+        }
+
+        let header = CrcCheck {
+            region: CrcRegion::Header,
+            expected: header_crc(&bytes[..HEADER_SIZE]),
+            stored: BigEndian::read_u32(&bytes[HEADER_CRC_OFFSET..HEADER_CRC_OFFSET + 4]),
+        };
+
+        let slots = std::array::from_fn(|i| {
+            let slot = &bytes[slot_range(i)];
+
+            CrcCheck {
+                region: CrcRegion::Slot(i),
+                expected: slot_crc(slot),
+                stored: BigEndian::read_u32(&slot[SLOT_CRC_OFFSET..SLOT_CRC_OFFSET + 4]),
+            }
+        });
+
+        Ok(Self { header, slots })
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.header.is_valid() && self.slots.iter().all(|s| s.is_valid())
+    }
+
+    /// All checks, header first.
+    pub fn checks(&self) -> impl Iterator<Item = &CrcCheck> {
+        std::iter::once(&self.header).chain(self.slots.iter())
+    }
+
+    /// Indices of the slots whose checksum does not match.
+    pub fn corrupt_slots(&self) -> Vec<usize> {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| !s.is_valid())
+            .map(|(i, _)| i)
+            .collect()
+    }
+}
+
+/// Rewrites every checksum in a raw save file so that it matches the data.
+pub fn repair_checksums(bytes: &mut [u8]) -> Result<(), SaveError> {
+    if bytes.len() < SAVE_FILE_SIZE {
+        return Err(SaveError::TooShort {
+            expected: SAVE_FILE_SIZE,
+            actual: bytes.len(),
+        });
+    }
+
+    let crc = header_crc(&bytes[..HEADER_SIZE]);
+    BigEndian::write_u32(&mut bytes[HEADER_CRC_OFFSET..HEADER_CRC_OFFSET + 4], crc);
+
+    for i in 0..SAVE_SLOT_COUNT {
+        let slot = &mut bytes[slot_range(i)];
+        let crc = slot_crc(slot);
+        BigEndian::write_u32(&mut slot[SLOT_CRC_OFFSET..SLOT_CRC_OFFSET + 4], crc);
+    }
+
+    Ok(())
+}
@@ -1,9 +1,14 @@
 use bitflags::bitflags;
 
 pub const HEADER_SIZE: usize = 0x6A0;
+pub const SAVE_SLOT_SIZE: usize = 0x980;
+pub const SAVE_SLOT_COUNT: usize = 6;
+pub const NORMAL_SLOT_COUNT: usize = 3; // the rest are quick save slots, one for each normal slot
+pub const SAVE_FILE_SIZE: usize = HEADER_SIZE + (SAVE_SLOT_SIZE * SAVE_SLOT_COUNT);
+pub const SAVE_VERSION: u8 = 0xE;
 pub const MAX_SCORE: u32 = 99999950;
 
-#[derive(Copy, Clone, PartialEq)]
+#[derive(Copy, Clone, PartialEq, Debug)]
 pub enum PlayerPowerup {
     None,
     Mushroom,
@@ -12,26 +17,84 @@ pub enum PlayerPowerup {
     PropellerMushroom,
     PenguinSuit,
     IceFlower,
+    Unknown(u8), // not a value the game writes, kept as-is
+}
+
+impl From<u8> for PlayerPowerup {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => PlayerPowerup::None,
+            1 => PlayerPowerup::Mushroom,
+            2 => PlayerPowerup::FireFlower,
+            3 => PlayerPowerup::MiniMushroom,
+            4 => PlayerPowerup::PropellerMushroom,
+            5 => PlayerPowerup::PenguinSuit,
+            6 => PlayerPowerup::IceFlower,
+            v => PlayerPowerup::Unknown(v),
+        }
+    }
+}
+
+impl From<PlayerPowerup> for u8 {
+    fn from(value: PlayerPowerup) -> Self {
+        match value {
+            PlayerPowerup::None => 0,
+            PlayerPowerup::Mushroom => 1,
+            PlayerPowerup::FireFlower => 2,
+            PlayerPowerup::MiniMushroom => 3,
+            PlayerPowerup::PropellerMushroom => 4,
+            PlayerPowerup::PenguinSuit => 5,
+            PlayerPowerup::IceFlower => 6,
+            PlayerPowerup::Unknown(v) => v,
+        }
+    }
 }
 
 pub const POWERUP_COUNT: usize = 7;
 
-#[derive(Copy, Clone, PartialEq)]
+#[derive(Copy, Clone, PartialEq, Debug)]
 pub enum PlayerCharacter {
     Mario,
     Luigi,
     BlueToad,
     YellowToad,
+    Unknown(u8), // not a value the game writes, kept as-is
+}
+
+impl From<u8> for PlayerCharacter {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => PlayerCharacter::Mario,
+            1 => PlayerCharacter::Luigi,
+            2 => PlayerCharacter::BlueToad,
+            3 => PlayerCharacter::YellowToad,
+            v => PlayerCharacter::Unknown(v),
+        }
+    }
+}
+
+impl From<PlayerCharacter> for u8 {
+    fn from(value: PlayerCharacter) -> Self {
+        match value {
+            PlayerCharacter::Mario => 0,
+            PlayerCharacter::Luigi => 1,
+            PlayerCharacter::BlueToad => 2,
+            PlayerCharacter::YellowToad => 3,
+            PlayerCharacter::Unknown(v) => v,
+        }
+    }
 }
 
 pub const PLAYER_COUNT: usize = 4;
+pub const STARTING_LIVES: u8 = 5;
 
 pub const STAGE_COUNT: usize = 42;
 pub const WORLD_COUNT: usize = 10;
 pub const ACTUAL_WORLD_COUNT: usize = 9;
+pub const MAX_SUBWORLD: u8 = 1; // only World 3 and a few others use the second one
 
 #[allow(clippy::upper_case_acronyms)]
-#[derive(PartialEq)]
+#[derive(Copy, Clone, PartialEq, Debug)]
 pub enum SaveFileRegion {
     NTSC,
     PAL,
@@ -41,13 +104,54 @@ pub enum SaveFileRegion {
     TW,
 }
 
+pub const REGION_COUNT: usize = 6;
+
+impl SaveFileRegion {
+    pub const ALL: [SaveFileRegion; REGION_COUNT] = [
+        SaveFileRegion::NTSC,
+        SaveFileRegion::PAL,
+        SaveFileRegion::JPN,
+        SaveFileRegion::KOR,
+        SaveFileRegion::CHN,
+        SaveFileRegion::TW,
+    ];
+
+    /// The region letter that follows the magic.
+    pub fn code(self) -> u8 {
+        match self {
+            SaveFileRegion::NTSC => b'E',
+            SaveFileRegion::PAL => b'P',
+            SaveFileRegion::JPN => b'J',
+            SaveFileRegion::KOR => b'K',
+            SaveFileRegion::CHN => b'C',
+            SaveFileRegion::TW => b'W',
+        }
+    }
+
+    pub fn from_code(code: u8) -> Option<Self> {
+        Self::ALL.into_iter().find(|r| r.code() == code)
+    }
+
+    /// Where the region's copies of the game are sold.
+    pub fn name(self) -> &'static str {
+        match self {
+            SaveFileRegion::NTSC => "North America",
+            SaveFileRegion::PAL => "Europe/Australia",
+            SaveFileRegion::JPN => "Japan",
+            SaveFileRegion::KOR => "Korea",
+            SaveFileRegion::CHN => "China",
+            SaveFileRegion::TW => "Taiwan",
+        }
+    }
+}
+
 pub const AMBUSH_ENEMY_COUNT: usize = 4;
 
 // there are 64 hint movies, but 70 is the constant
 pub const HINT_MOVIE_COUNT: usize = 70;
 pub const ACTUAL_HINT_MOVIE_COUNT: usize = 64;
 
-#[derive(Copy, Clone, PartialEq)]
+#[derive(Copy, Clone, PartialEq, Debug)]
 pub enum StartingMushroomKind {
     None,
     Star,
@@ -56,13 +160,67 @@ pub enum StartingMushroomKind {
     StarRescue,
     ItemRescue,
     OneUpRescue,
+    Unknown(u8), // not a value the game writes, kept as-is
+}
+
+impl From<u8> for StartingMushroomKind {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => StartingMushroomKind::None,
+            1 => StartingMushroomKind::Star,
+            2 => StartingMushroomKind::Item,
+            3 => StartingMushroomKind::OneUp,
+            4 => StartingMushroomKind::StarRescue,
+            5 => StartingMushroomKind::ItemRescue,
+            6 => StartingMushroomKind::OneUpRescue,
+            v => StartingMushroomKind::Unknown(v),
+        }
+    }
+}
+
+impl From<StartingMushroomKind> for u8 {
+    fn from(value: StartingMushroomKind) -> Self {
+        match value {
+            StartingMushroomKind::None => 0,
+            StartingMushroomKind::Star => 1,
+            StartingMushroomKind::Item => 2,
+            StartingMushroomKind::OneUp => 3,
+            StartingMushroomKind::StarRescue => 4,
+            StartingMushroomKind::ItemRescue => 5,
+            StartingMushroomKind::OneUpRescue => 6,
+            StartingMushroomKind::Unknown(v) => v,
+        }
+    }
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq, Debug)]
 pub enum EnemyDirection {
     ToNextNode,     // "forwards" to the next node
     ToPreviousNode, // "backwards" to the previous node
     FirstTimeValue, // the initial value that was set prior to entering the world for the first time
+    Unknown(u8),    // not a value the game writes, kept as-is
+}
+
+impl From<u8> for EnemyDirection {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => EnemyDirection::ToNextNode,
+            1 => EnemyDirection::ToPreviousNode,
+            2 => EnemyDirection::FirstTimeValue,
+            v => EnemyDirection::Unknown(v),
+        }
+    }
+}
+
+impl From<EnemyDirection> for u8 {
+    fn from(value: EnemyDirection) -> Self {
+        match value {
+            EnemyDirection::ToNextNode => 0,
+            EnemyDirection::ToPreviousNode => 1,
+            EnemyDirection::FirstTimeValue => 2,
+            EnemyDirection::Unknown(v) => v,
+        }
+    }
 }
 
 bitflags! {
@@ -187,5 +345,6 @@ pub const PLAYER_POWERUP_STATUS: [&str; 7] = [
 
 pub const POWERUP_STOCK_MAX: u8 = 99;
 pub const PLAYER_LIFE_MAX: u8 = 99;
+pub const SUPER_GUIDE_DEATH_COUNT: u8 = 8; // deaths in a stage before the Super Guide block appears
 
 pub const PLAYER_NAMES: [&str; 4] = ["Mario", "Luigi", "Blue Toad", "Yellow Toad"];
@@ -0,0 +1,707 @@
+use bitflags::Flags;
+use std::fmt;
+
+use crate::savefile::constants::*;
+use crate::savefile::saveheader::SaveHeader;
+use crate::savefile::saveslot::SaveSlot;
+use crate::savefile::SaveFile;
+
+// every field is addressed by a path such as `header.last_selected_index`,
+// `slot[0].player_lives[2]` or `slot[1].stage_completion_flags[3][21]`.
+// values are handled as a raw u32 and only turned into text at the edges.
+
+/// How the raw value of a field is shown and parsed.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum FieldKind {
+    U8,
+    U16,
+    U32,
+    Bool,
+    Region,
+    Character,
+    Powerup,
+    MushroomHouse,
+    EnemyDirection,
+    GameFlags,
+    SpawnFlags,
+    StageFlags,
+}
+
+#[derive(Debug)]
+pub enum FieldError {
+    BadPath(String),
+    UnknownField(String),
+    IndexOutOfRange {
+        path: String,
+        index: usize,
+        len: usize,
+    },
+    BadValue {
+        path: String,
+        value: String,
+        kind: FieldKind,
+    },
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldError::BadPath(path) => write!(f, "malformed field path: {}", path),
+            FieldError::UnknownField(path) => write!(f, "no such field: {}", path),
+            FieldError::IndexOutOfRange { path, index, len } => write!(
+                f,
+                "index {} is out of range in {} (length is {})",
+                index, path, len
+            ),
+            FieldError::BadValue { path, value, kind } => {
+                write!(
+                    f,
+                    "{:?} is not a valid {:?} value for {}",
+                    value, kind, path
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for FieldError {}
+
+pub struct FieldInfo<T> {
+    pub name: &'static str,
+    pub dims: &'static [usize],
+    pub kind: FieldKind,
+    get: fn(&T, &[usize]) -> u32,
+    set: fn(&mut T, &[usize], u32),
+}
+
+impl<T> FieldInfo<T> {
+    pub fn get(&self, target: &T, index: &[usize]) -> u32 {
+        (self.get)(target, index)
+    }
+
+    pub fn set(&self, target: &mut T, index: &[usize], value: u32) {
+        (self.set)(target, index, value)
+    }
+}
+
+/// A single value in a save file.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Field {
+    pub path: String,
+    pub name: &'static str,
+    pub index: Vec<usize>,
+    pub kind: FieldKind,
+    pub value: u32,
+}
+
+impl Field {
+    pub fn display_value(&self) -> String {
+        self.kind.format(self.value)
+    }
+}
+
+pub const HEADER_FIELDS: &[FieldInfo<SaveHeader>] = &[
+    FieldInfo {
+        name: "region",
+        dims: &[],
+        kind: FieldKind::Region,
+        get: |h, _| h.region.code() as u32,
+        set: |h, _, v| {
+            if let Some(r) = SaveFileRegion::from_code(v as u8) {
+                h.region = r;
+            }
+        },
+    },
+    FieldInfo {
+        name: "last_selected_index",
+        dims: &[],
+        kind: FieldKind::U8,
+        get: |h, _| h.last_selected_index as u32,
+        set: |h, _, v| h.last_selected_index = v as u8,
+    },
+    FieldInfo {
+        name: "free_mode_play_count",
+        dims: &[WORLD_COUNT, STAGE_COUNT],
+        kind: FieldKind::U16,
+        get: |h, i| h.free_mode_play_count[i[0]][i[1]] as u32,
+        set: |h, i, v| h.free_mode_play_count[i[0]][i[1]] = v as u16,
+    },
+    FieldInfo {
+        name: "coin_battle_play_count",
+        dims: &[WORLD_COUNT, STAGE_COUNT],
+        kind: FieldKind::U16,
+        get: |h, i| h.coin_battle_play_count[i[0]][i[1]] as u32,
+        set: |h, i, v| h.coin_battle_play_count[i[0]][i[1]] = v as u16,
+    },
+    FieldInfo {
+        name: "extra_modes_unlocked_worlds",
+        dims: &[],
+        kind: FieldKind::U16,
+        get: |h, _| h.extra_modes_unlocked_worlds as u32,
+        set: |h, _, v| h.extra_modes_unlocked_worlds = v as u16,
+    },
+];
+
+pub const SLOT_FIELDS: &[FieldInfo<SaveSlot>] = &[
+    FieldInfo {
+        name: "game_completion_flags",
+        dims: &[],
+        kind: FieldKind::GameFlags,
+        get: |s, _| s.game_completion_flags as u32,
+        set: |s, _, v| s.game_completion_flags = v as u8,
+    },
+    FieldInfo {
+        name: "cur_world",
+        dims: &[],
+        kind: FieldKind::U8,
+        get: |s, _| s.cur_world as u32,
+        set: |s, _, v| s.cur_world = v as u8,
+    },
+    FieldInfo {
+        name: "cur_subworld",
+        dims: &[],
+        kind: FieldKind::U8,
+        get: |s, _| s.cur_subworld as u32,
+        set: |s, _, v| s.cur_subworld = v as u8,
+    },
+    FieldInfo {
+        name: "cur_path_node",
+        dims: &[],
+        kind: FieldKind::U8,
+        get: |s, _| s.cur_path_node as u32,
+        set: |s, _, v| s.cur_path_node = v as u8,
+    },
+    FieldInfo {
+        name: "w5_vine_reshuffle_counter",
+        dims: &[],
+        kind: FieldKind::U8,
+        get: |s, _| s.w5_vine_reshuffle_counter as u32,
+        set: |s, _, v| s.w5_vine_reshuffle_counter = v as u8,
+    },
+    FieldInfo {
+        name: "w3_switch_on",
+        dims: &[],
+        kind: FieldKind::Bool,
+        get: |s, _| s.w3_switch_on as u32,
+        set: |s, _, v| s.w3_switch_on = v != 0,
+    },
+    FieldInfo {
+        name: "item_stock",
+        dims: &[POWERUP_COUNT],
+        kind: FieldKind::U8,
+        get: |s, i| s.item_stock[i[0]] as u32,
+        set: |s, i, v| s.item_stock[i[0]] = v as u8,
+    },
+    FieldInfo {
+        name: "starting_mushroom_house_type",
+        dims: &[WORLD_COUNT],
+        kind: FieldKind::MushroomHouse,
+        get: |s, i| u8::from(s.starting_mushroom_house_type[i[0]]) as u32,
+        set: |s, i, v| s.starting_mushroom_house_type[i[0]] = (v as u8).into(),
+    },
+    FieldInfo {
+        name: "player_continues",
+        dims: &[PLAYER_COUNT],
+        kind: FieldKind::U8,
+        get: |s, i| s.player_continues[i[0]] as u32,
+        set: |s, i, v| s.player_continues[i[0]] = v as u8,
+    },
+    FieldInfo {
+        name: "player_coins",
+        dims: &[PLAYER_COUNT],
+        kind: FieldKind::U8,
+        get: |s, i| s.player_coins[i[0]] as u32,
+        set: |s, i, v| s.player_coins[i[0]] = v as u8,
+    },
+    FieldInfo {
+        name: "player_lives",
+        dims: &[PLAYER_COUNT],
+        kind: FieldKind::U8,
+        get: |s, i| s.player_lives[i[0]] as u32,
+        set: |s, i, v| s.player_lives[i[0]] = v as u8,
+    },
+    FieldInfo {
+        name: "player_spawn_flags",
+        dims: &[PLAYER_COUNT],
+        kind: FieldKind::SpawnFlags,
+        get: |s, i| s.player_spawn_flags[i[0]] as u32,
+        set: |s, i, v| s.player_spawn_flags[i[0]] = v as u8,
+    },
+    FieldInfo {
+        name: "player_character",
+        dims: &[PLAYER_COUNT],
+        kind: FieldKind::Character,
+        get: |s, i| u8::from(s.player_character[i[0]]) as u32,
+        set: |s, i, v| s.player_character[i[0]] = (v as u8).into(),
+    },
+    FieldInfo {
+        name: "player_powerup",
+        dims: &[PLAYER_COUNT],
+        kind: FieldKind::Powerup,
+        get: |s, i| u8::from(s.player_powerup[i[0]]) as u32,
+        set: |s, i, v| s.player_powerup[i[0]] = (v as u8).into(),
+    },
+    FieldInfo {
+        name: "world_unlocked",
+        dims: &[WORLD_COUNT],
+        kind: FieldKind::Bool,
+        get: |s, i| s.world_unlocked[i[0]] as u32,
+        set: |s, i, v| s.world_unlocked[i[0]] = v != 0,
+    },
+    FieldInfo {
+        name: "enemy_revival_count",
+        dims: &[WORLD_COUNT, AMBUSH_ENEMY_COUNT],
+        kind: FieldKind::U8,
+        get: |s, i| s.enemy_revival_count[i[0]][i[1]] as u32,
+        set: |s, i, v| s.enemy_revival_count[i[0]][i[1]] = v as u8,
+    },
+    FieldInfo {
+        name: "staff_credits_high_score",
+        dims: &[],
+        kind: FieldKind::U16,
+        get: |s, _| s.staff_credits_high_score as u32,
+        set: |s, _, v| s.staff_credits_high_score = v as u16,
+    },
+    FieldInfo {
+        name: "ingame_score",
+        dims: &[],
+        kind: FieldKind::U32,
+        get: |s, _| s.ingame_score,
+        set: |s, _, v| s.ingame_score = v,
+    },
+    FieldInfo {
+        name: "stage_completion_flags",
+        dims: &[WORLD_COUNT, STAGE_COUNT],
+        kind: FieldKind::StageFlags,
+        get: |s, i| s.stage_completion_flags[i[0]][i[1]],
+        set: |s, i, v| s.stage_completion_flags[i[0]][i[1]] = v,
+    },
+    FieldInfo {
+        name: "hint_movie_bought",
+        dims: &[HINT_MOVIE_COUNT],
+        kind: FieldKind::Bool,
+        get: |s, i| s.hint_movie_bought[i[0]] as u32,
+        set: |s, i, v| s.hint_movie_bought[i[0]] = v != 0,
+    },
+    FieldInfo {
+        name: "toad_rescue_level",
+        dims: &[WORLD_COUNT],
+        kind: FieldKind::U8,
+        get: |s, i| s.toad_rescue_level[i[0]] as u32,
+        set: |s, i, v| s.toad_rescue_level[i[0]] = v as u8,
+    },
+    FieldInfo {
+        name: "enemy_subworld",
+        dims: &[WORLD_COUNT, AMBUSH_ENEMY_COUNT],
+        kind: FieldKind::U8,
+        get: |s, i| s.enemy_subworld[i[0]][i[1]] as u32,
+        set: |s, i, v| s.enemy_subworld[i[0]][i[1]] = v as u8,
+    },
+    FieldInfo {
+        name: "enemy_pos_index",
+        dims: &[WORLD_COUNT, AMBUSH_ENEMY_COUNT],
+        kind: FieldKind::U8,
+        get: |s, i| s.enemy_pos_index[i[0]][i[1]] as u32,
+        set: |s, i, v| s.enemy_pos_index[i[0]][i[1]] = v as u8,
+    },
+    FieldInfo {
+        name: "enemy_walk_direction",
+        dims: &[WORLD_COUNT, AMBUSH_ENEMY_COUNT],
+        kind: FieldKind::EnemyDirection,
+        get: |s, i| u8::from(s.enemy_walk_direction[i[0]][i[1]]) as u32,
+        set: |s, i, v| s.enemy_walk_direction[i[0]][i[1]] = (v as u8).into(),
+    },
+    FieldInfo {
+        name: "player_death_count",
+        dims: &[WORLD_COUNT, STAGE_COUNT],
+        kind: FieldKind::U8,
+        get: |s, i| s.player_death_count[i[0]][i[1]] as u32,
+        set: |s, i, v| s.player_death_count[i[0]][i[1]] = v as u8,
+    },
+    FieldInfo {
+        name: "player_death_count_w3_l4_switch",
+        dims: &[],
+        kind: FieldKind::U8,
+        get: |s, _| s.player_death_count_w3_l4_switch as u32,
+        set: |s, _, v| s.player_death_count_w3_l4_switch = v as u8,
+    },
+];
+
+fn parse_number(s: &str) -> Option<u32> {
+    let s = s.trim();
+
+    match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => s.parse().ok(),
+    }
+}
+
+/// The name and value of every documented bit of a flags type.
+pub fn flag_table<F: Flags>() -> Vec<(&'static str, u32)>
+where
+    F::Bits: Into<u32>,
+{
+    F::FLAGS
+        .iter()
+        .map(|f| (f.name(), f.value().bits().into()))
+        .collect()
+}
+
+fn format_flags(table: &[(&'static str, u32)], value: u32) -> String {
+    let mut parts: Vec<String> = table
+        .iter()
+        .filter(|(_, bit)| value & bit != 0)
+        .map(|(name, _)| name.to_string())
+        .collect();
+
+    let known = table.iter().fold(0, |acc, (_, bit)| acc | bit);
+
+    if value & !known != 0 {
+        parts.push(format!("{:#X}", value & !known));
+    }
+
+    if parts.is_empty() {
+        String::from("0")
+    } else {
+        parts.join(" | ")
+    }
+}
+
+fn parse_flags(table: &[(&'static str, u32)], s: &str) -> Option<u32> {
+    let mut value = 0;
+
+    for part in s.split('|') {
+        let part = part.trim();
+
+        value |= match table
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(part))
+        {
+            Some((_, bit)) => *bit,
+            None => parse_number(part)?,
+        };
+    }
+
+    Some(value)
+}
+
+// byte-backed enums are shown by their variant name, or as a number if unknown
+fn format_enum<E: From<u8> + fmt::Debug>(value: u32, is_unknown: fn(&E) -> bool) -> String {
+    let e = E::from(value as u8);
+
+    if is_unknown(&e) {
+        value.to_string()
+    } else {
+        format!("{:?}", e)
+    }
+}
+
+fn parse_enum<E: From<u8> + fmt::Debug>(s: &str, is_unknown: fn(&E) -> bool) -> Option<u32> {
+    let s = s.trim();
+
+    for code in 0..=u8::MAX {
+        let e = E::from(code);
+
+        if is_unknown(&e) {
+            break;
+        }
+
+        if format!("{:?}", e).eq_ignore_ascii_case(s) {
+            return Some(code as u32);
+        }
+    }
+
+    parse_number(s).filter(|v| *v <= u8::MAX as u32)
+}
+
+impl FieldKind {
+    pub fn flag_table(self) -> Option<Vec<(&'static str, u32)>> {
+        match self {
+            FieldKind::GameFlags => Some(flag_table::<GameCompletionFlags>()),
+            FieldKind::SpawnFlags => Some(flag_table::<PlayerCreationFlags>()),
+            FieldKind::StageFlags => Some(flag_table::<StageCompletionFlags>()),
+            _ => None,
+        }
+    }
+
+    pub fn max(self) -> u32 {
+        match self {
+            FieldKind::U16 => u16::MAX as u32,
+            FieldKind::U32 | FieldKind::StageFlags => u32::MAX,
+            FieldKind::Bool => 1,
+            _ => u8::MAX as u32,
+        }
+    }
+
+    pub fn format(self, value: u32) -> String {
+        match self {
+            FieldKind::U8 | FieldKind::U16 | FieldKind::U32 => value.to_string(),
+            FieldKind::Bool => (value != 0).to_string(),
+            FieldKind::Region => match SaveFileRegion::from_code(value as u8) {
+                Some(r) => format!("{:?}", r),
+                None => value.to_string(),
+            },
+            FieldKind::Character => {
+                format_enum::<PlayerCharacter>(value, |e| matches!(e, PlayerCharacter::Unknown(_)))
+            }
+            FieldKind::Powerup => {
+                format_enum::<PlayerPowerup>(value, |e| matches!(e, PlayerPowerup::Unknown(_)))
+            }
+            FieldKind::MushroomHouse => format_enum::<StartingMushroomKind>(value, |e| {
+                matches!(e, StartingMushroomKind::Unknown(_))
+            }),
+            FieldKind::EnemyDirection => {
+                format_enum::<EnemyDirection>(value, |e| matches!(e, EnemyDirection::Unknown(_)))
+            }
+            FieldKind::GameFlags | FieldKind::SpawnFlags | FieldKind::StageFlags => {
+                format_flags(&self.flag_table().unwrap_or_default(), value)
+            }
+        }
+    }
+
+    pub fn parse(self, s: &str) -> Option<u32> {
+        let value = match self {
+            FieldKind::U8 | FieldKind::U16 | FieldKind::U32 => parse_number(s)?,
+            FieldKind::Bool => match s.trim().to_lowercase().as_str() {
+                "true" | "1" => 1,
+                "false" | "0" => 0,
+                _ => return None,
+            },
+            FieldKind::Region => SaveFileRegion::ALL
+                .into_iter()
+                .find(|r| format!("{:?}", r).eq_ignore_ascii_case(s.trim()))?
+                .code() as u32,
+            FieldKind::Character => {
+                parse_enum::<PlayerCharacter>(s, |e| matches!(e, PlayerCharacter::Unknown(_)))?
+            }
+            FieldKind::Powerup => {
+                parse_enum::<PlayerPowerup>(s, |e| matches!(e, PlayerPowerup::Unknown(_)))?
+            }
+            FieldKind::MushroomHouse => parse_enum::<StartingMushroomKind>(s, |e| {
+                matches!(e, StartingMushroomKind::Unknown(_))
+            })?,
+            FieldKind::EnemyDirection => {
+                parse_enum::<EnemyDirection>(s, |e| matches!(e, EnemyDirection::Unknown(_)))?
+            }
+            FieldKind::GameFlags | FieldKind::SpawnFlags | FieldKind::StageFlags => {
+                parse_flags(&self.flag_table().unwrap_or_default(), s)?
+            }
+        };
+
+        if value > self.max() {
+            return None;
+        }
+
+        Some(value)
+    }
+}
+
+/// Every combination of indices for a field with the given dimensions.
+pub fn all_indices(dims: &[usize]) -> Vec<Vec<usize>> {
+    let mut out = vec![Vec::new()];
+
+    for &len in dims {
+        out = out
+            .into_iter()
+            .flat_map(|prefix| {
+                (0..len).map(move |i| {
+                    let mut index = prefix.clone();
+                    index.push(i);
+                    index
+                })
+            })
+            .collect();
+    }
+
+    out
+}
+
+pub fn format_path(prefix: &str, name: &str, index: &[usize]) -> String {
+    let mut path = format!("{}.{}", prefix, name);
+
+    for i in index {
+        path += &format!("[{}]", i);
+    }
+
+    path
+}
+
+// splits "name[1][2]" into ("name", [1, 2])
+fn split_indices(s: &str) -> Option<(&str, Vec<usize>)> {
+    let (name, mut rest) = match s.find('[') {
+        Some(i) => (&s[..i], &s[i..]),
+        None => (s, ""),
+    };
+
+    let mut index = Vec::new();
+
+    while !rest.is_empty() {
+        let close = rest.find(']')?;
+        index.push(rest.strip_prefix('[')?[..close - 1].trim().parse().ok()?);
+        rest = &rest[close + 1..];
+    }
+
+    Some((name, index))
+}
+
+fn lookup<T>(
+    fields: &'static [FieldInfo<T>],
+    s: &str,
+    path: &str,
+) -> Result<(&'static FieldInfo<T>, Vec<usize>), FieldError> {
+    let (name, index) = split_indices(s).ok_or_else(|| FieldError::BadPath(path.to_string()))?;
+
+    let field = fields
+        .iter()
+        .find(|f| f.name == name)
+        .ok_or_else(|| FieldError::UnknownField(path.to_string()))?;
+
+    if index.len() != field.dims.len() {
+        return Err(FieldError::BadPath(path.to_string()));
+    }
+
+    for (&i, &len) in index.iter().zip(field.dims.iter()) {
+        if i >= len {
+            return Err(FieldError::IndexOutOfRange {
+                path: path.to_string(),
+                index: i,
+                len,
+            });
+        }
+    }
+
+    Ok((field, index))
+}
+
+enum Target {
+    Header(&'static FieldInfo<SaveHeader>, Vec<usize>),
+    Slot(usize, &'static FieldInfo<SaveSlot>, Vec<usize>),
+}
+
+fn resolve(path: &str) -> Result<Target, FieldError> {
+    let path = path.trim();
+
+    if let Some(rest) = path.strip_prefix("header.") {
+        let (field, index) = lookup(HEADER_FIELDS, rest, path)?;
+
+        return Ok(Target::Header(field, index));
+    }
+
+    let (head, rest) = path
+        .split_once('.')
+        .ok_or_else(|| FieldError::BadPath(path.to_string()))?;
+
+    match split_indices(head) {
+        Some(("slot", slot)) if slot.len() == 1 => {
+            if slot[0] >= SAVE_SLOT_COUNT {
+                return Err(FieldError::IndexOutOfRange {
+                    path: path.to_string(),
+                    index: slot[0],
+                    len: SAVE_SLOT_COUNT,
+                });
+            }
+
+            let (field, index) = lookup(SLOT_FIELDS, rest, path)?;
+
+            Ok(Target::Slot(slot[0], field, index))
+        }
+
+        _ => Err(FieldError::BadPath(path.to_string())),
+    }
+}
+
+fn collect<T>(out: &mut Vec<Field>, prefix: &str, fields: &[FieldInfo<T>], target: &T) {
+    for field in fields {
+        for index in all_indices(field.dims) {
+            out.push(Field {
+                path: format_path(prefix, field.name, &index),
+                name: field.name,
+                kind: field.kind,
+                value: field.get(target, &index),
+                index,
+            });
+        }
+    }
+}
+
+impl SaveFile {
+    /// Every field of the header, in file order.
+    pub fn header_fields(&self) -> Vec<Field> {
+        let mut out = Vec::new();
+        collect(&mut out, "header", HEADER_FIELDS, &self.header);
+
+        out
+    }
+
+    /// Every field of one save slot, in file order.
+    pub fn slot_fields(&self, slot: usize) -> Vec<Field> {
+        let mut out = Vec::new();
+        collect(
+            &mut out,
+            &format!("slot[{}]", slot),
+            SLOT_FIELDS,
+            &self.save_slots[slot],
+        );
+
+        out
+    }
+
+    /// Every field of the header followed by every field of each slot.
+    pub fn fields(&self) -> Vec<Field> {
+        let mut out = self.header_fields();
+
+        for i in 0..SAVE_SLOT_COUNT {
+            out.append(&mut self.slot_fields(i));
+        }
+
+        out
+    }
+
+    pub fn get_field(&self, path: &str) -> Result<Field, FieldError> {
+        let (name, kind, value, index) = match resolve(path)? {
+            Target::Header(field, index) => (
+                field.name,
+                field.kind,
+                field.get(&self.header, &index),
+                index,
+            ),
+            Target::Slot(slot, field, index) => (
+                field.name,
+                field.kind,
+                field.get(&self.save_slots[slot], &index),
+                index,
+            ),
+        };
+
+        Ok(Field {
+            path: path.trim().to_string(),
+            name,
+            index,
+            kind,
+            value,
+        })
+    }
+
+    /// Parses `value` according to the kind of field at `path` and stores it.
+    pub fn set_field(&mut self, path: &str, value: &str) -> Result<(), FieldError> {
+        let target = resolve(path)?;
+
+        let kind = match &target {
+            Target::Header(field, _) => field.kind,
+            Target::Slot(_, field, _) => field.kind,
+        };
+
+        let raw = kind.parse(value).ok_or_else(|| FieldError::BadValue {
+            path: path.trim().to_string(),
+            value: value.to_string(),
+            kind,
+        })?;
+
+        match target {
+            Target::Header(field, index) => field.set(&mut self.header, &index, raw),
+            Target::Slot(slot, field, index) => field.set(&mut self.save_slots[slot], &index, raw),
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,294 @@
+use std::fmt;
+
+use json::JsonValue;
+
+use crate::savefile::constants::*;
+use crate::savefile::slotops::SlotError;
+use crate::savefile::stages::{self, SPECIAL_WORLD};
+use crate::savefile::SaveFile;
+
+/// A story progress state that can be applied to a slot in one go.
+///
+/// Worlds are unlocked up to the one after the last cleared world, plus World 9 once
+/// the final boss is beaten, so that a preset never describes progress the game can't
+/// reach.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Preset {
+    pub name: String,
+    pub description: String,
+    /// How many worlds have every course cleared, counting from World 1.
+    pub cleared_worlds: usize,
+    /// Whether the cleared worlds have their secret exits taken too.
+    pub secret_exits: bool,
+    /// Whether the cleared worlds have all their star coins collected.
+    pub star_coins: bool,
+    pub final_boss_beaten: bool,
+    /// Whether every hint movie of an unlocked world is bought.
+    pub hint_movies: bool,
+    /// The world the player is in, or `None` for the last unlocked one.
+    pub cur_world: Option<usize>,
+}
+
+#[derive(Debug)]
+pub enum PresetError {
+    Parse(json::Error),
+    Invalid { preset: String, message: String },
+}
+
+impl fmt::Display for PresetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PresetError::Parse(e) => write!(f, "invalid json: {}", e),
+            PresetError::Invalid { preset, message } => {
+                write!(f, "preset {:?}: {}", preset, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PresetError {}
+
+impl Preset {
+    fn new(name: &str, description: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            description: description.to_string(),
+            cleared_worlds: 0,
+            secret_exits: false,
+            star_coins: false,
+            final_boss_beaten: false,
+            hint_movies: false,
+            cur_world: None,
+        }
+    }
+
+    /// Whether world `world` is unlocked with this preset.
+    pub fn unlocks(&self, world: usize) -> bool {
+        if world == SPECIAL_WORLD {
+            self.final_boss_beaten
+        } else {
+            world <= self.cleared_worlds.min(SPECIAL_WORLD - 1)
+        }
+    }
+
+    pub fn current_world(&self) -> usize {
+        self.cur_world.unwrap_or_else(|| {
+            (0..ACTUAL_WORLD_COUNT)
+                .rev()
+                .find(|&w| self.unlocks(w))
+                .unwrap_or(0)
+        })
+    }
+
+    /// Checks that the preset describes progress the game can reach.
+    pub fn check(&self) -> Result<(), PresetError> {
+        let invalid = |message: String| {
+            Err(PresetError::Invalid {
+                preset: self.name.clone(),
+                message,
+            })
+        };
+
+        if self.cleared_worlds > ACTUAL_WORLD_COUNT {
+            return invalid(format!(
+                "cleared_worlds is {}, but there are only {} worlds",
+                self.cleared_worlds, ACTUAL_WORLD_COUNT
+            ));
+        }
+
+        if self.final_boss_beaten && self.cleared_worlds < SPECIAL_WORLD {
+            return invalid(format!(
+                "the final boss is in World {}, but only {} world(s) are cleared",
+                SPECIAL_WORLD, self.cleared_worlds
+            ));
+        }
+
+        if self.cleared_worlds > SPECIAL_WORLD && !self.final_boss_beaten {
+            return invalid(format!(
+                "World {} is cleared, but the final boss hasn't been beaten",
+                SPECIAL_WORLD + 1
+            ));
+        }
+
+        if let Some(w) = self.cur_world {
+            if w >= ACTUAL_WORLD_COUNT || !self.unlocks(w) {
+                return invalid(format!("current_world {} isn't unlocked", w + 1));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads a preset from an object like
+    /// `{"name": "...", "cleared_worlds": 5, "star_coins": true, "current_world": 3}`.
+    /// Worlds are numbered from 1 and missing flags are `false`.
+    pub fn from_json(v: &JsonValue) -> Result<Self, PresetError> {
+        let name = v["name"].as_str().unwrap_or_default().to_string();
+
+        let invalid = |message: &str| PresetError::Invalid {
+            preset: name.clone(),
+            message: message.to_string(),
+        };
+
+        if name.is_empty() {
+            return Err(invalid("needs a name"));
+        }
+
+        let mut preset = Preset::new(&name, v["description"].as_str().unwrap_or_default());
+
+        preset.cleared_worlds = match &v["cleared_worlds"] {
+            JsonValue::Null => 0,
+            n => n
+                .as_usize()
+                .ok_or_else(|| invalid("cleared_worlds must be a number"))?,
+        };
+
+        let flag = |key: &str| match &v[key] {
+            JsonValue::Null => Ok(false),
+            b => b
+                .as_bool()
+                .ok_or_else(|| invalid(&format!("{} must be true or false", key))),
+        };
+
+        preset.secret_exits = flag("secret_exits")?;
+        preset.star_coins = flag("star_coins")?;
+        preset.final_boss_beaten = flag("final_boss_beaten")?;
+        preset.hint_movies = flag("hint_movies")?;
+
+        preset.cur_world = match &v["current_world"] {
+            JsonValue::Null => None,
+            n => match n.as_usize() {
+                Some(w) if w >= 1 => Some(w - 1),
+                _ => return Err(invalid("current_world must be a world number")),
+            },
+        };
+
+        preset.check()?;
+
+        Ok(preset)
+    }
+}
+
+/// The presets that come with the editor.
+pub fn builtin_presets() -> Vec<Preset> {
+    let mut presets = Vec::new();
+
+    presets.push(Preset {
+        cleared_worlds: ACTUAL_WORLD_COUNT,
+        secret_exits: true,
+        star_coins: true,
+        final_boss_beaten: true,
+        hint_movies: true,
+        ..Preset::new(
+            "100%",
+            "Every goal, every star coin and every hint movie, with the game completed.",
+        )
+    });
+
+    for w in 0..SPECIAL_WORLD {
+        presets.push(Preset {
+            cleared_worlds: w,
+            ..Preset::new(
+                &format!("World {} start", w + 1),
+                &format!(
+                    "Just reached World {}, with nothing collected on the way.",
+                    w + 1
+                ),
+            )
+        });
+    }
+
+    presets.push(Preset {
+        cleared_worlds: SPECIAL_WORLD,
+        final_boss_beaten: true,
+        cur_world: Some(0),
+        ..Preset::new(
+            "All worlds, no star coins",
+            "Every world unlocked and the final boss beaten, without a single star coin.",
+        )
+    });
+
+    presets
+}
+
+/// Reads user presets from a json array of the objects [`Preset::from_json`] takes.
+pub fn load_presets(input: &str) -> Result<Vec<Preset>, PresetError> {
+    let root = json::parse(input).map_err(PresetError::Parse)?;
+
+    if !root.is_array() {
+        return Err(PresetError::Invalid {
+            preset: String::new(),
+            message: String::from("the file must contain a list of presets"),
+        });
+    }
+
+    root.members().map(Preset::from_json).collect()
+}
+
+impl SaveFile {
+    /// Replaces the story progress of slot `index` with `preset`. An empty slot is
+    /// started as a new game first; everything the preset doesn't cover is kept.
+    pub fn apply_preset(&mut self, index: usize, preset: &Preset) -> Result<(), SlotError> {
+        if index >= SAVE_SLOT_COUNT {
+            return Err(SlotError::InvalidIndex(index));
+        }
+
+        if self.save_slots[index].is_empty() {
+            self.start_new_game(index)?;
+        }
+
+        let slot = &mut self.save_slots[index];
+
+        slot.stage_completion_flags = [[0; STAGE_COUNT]; WORLD_COUNT];
+
+        for w in 0..preset.cleared_worlds {
+            for course in stages::world_courses(w) {
+                let mut flags = StageCompletionFlags::GoalNormal;
+
+                if preset.secret_exits && course.secret_exit {
+                    flags |= StageCompletionFlags::GoalSecret;
+                }
+
+                if preset.star_coins {
+                    flags |= StageCompletionFlags::StarCoin1
+                        | StageCompletionFlags::StarCoin2
+                        | StageCompletionFlags::StarCoin3;
+                }
+
+                slot.stage_completion_flags[w][course.stage] = flags.bits();
+            }
+        }
+
+        for w in 0..WORLD_COUNT {
+            slot.world_unlocked[w] = preset.unlocks(w);
+        }
+
+        for m in 0..HINT_MOVIE_COUNT {
+            let world = stages::hint_movie_world(m);
+
+            slot.hint_movie_bought[m] =
+                preset.hint_movies && world.is_some_and(|w| preset.unlocks(w));
+        }
+
+        // the super guide flag is left alone, it isn't progress
+        let mut flags = GameCompletionFlags::from_bits_retain(slot.game_completion_flags);
+        flags.set(
+            GameCompletionFlags::FinalBossBeaten,
+            preset.final_boss_beaten,
+        );
+        slot.game_completion_flags = flags.bits();
+        slot.sync_completion_flags();
+
+        // the path node belongs to the old world's map
+        slot.cur_world = preset.current_world() as u8;
+        slot.cur_subworld = 0;
+        slot.cur_path_node = 0;
+
+        // other slots may have unlocked more, so only ever add worlds
+        for w in (0..ACTUAL_WORLD_COUNT).filter(|&w| preset.unlocks(w)) {
+            self.header.extra_modes_unlocked_worlds |= 1 << w;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,410 @@
+use crate::savefile::constants::*;
+use crate::savefile::saveslot::SaveSlot;
+use crate::savefile::savevalidate::{Problem, Severity};
+use crate::savefile::stages::{self, stage_name, CANNON, SPECIAL_WORLD};
+use crate::savefile::SaveFile;
+
+/// A change that makes a finding go away.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Fix {
+    LockWorld(usize),
+    SetCurrentWorld(usize),
+    ClearGameFlags(GameCompletionFlags),
+    SetStageFlags(usize, usize, StageCompletionFlags),
+    UnbuyHintMovie(usize),
+}
+
+impl Fix {
+    pub fn description(&self) -> String {
+        match *self {
+            Fix::LockWorld(w) => format!("Lock World {}", w + 1),
+            Fix::SetCurrentWorld(w) => format!("Move to World {}", w + 1),
+            Fix::ClearGameFlags(flags) => format!("Clear {}", flag_names(flags)),
+            Fix::SetStageFlags(w, st, flags) => {
+                format!("Set {} in {}", flag_names(flags), stage_name(w, st))
+            }
+            Fix::UnbuyHintMovie(m) => format!("Un-buy \"{}\"", HINT_MOVIE_TITLES[m]),
+        }
+    }
+
+    pub fn apply(&self, slot: &mut SaveSlot) {
+        match *self {
+            Fix::LockWorld(w) => slot.world_unlocked[w] = false,
+            Fix::SetCurrentWorld(w) => {
+                // the path node belongs to the old world's map
+                slot.cur_world = w as u8;
+                slot.cur_subworld = 0;
+                slot.cur_path_node = 0;
+            }
+            Fix::ClearGameFlags(flags) => slot.game_completion_flags &= !flags.bits(),
+            Fix::SetStageFlags(w, st, flags) => slot.stage_completion_flags[w][st] |= flags.bits(),
+            Fix::UnbuyHintMovie(m) => slot.hint_movie_bought[m] = false,
+        }
+    }
+}
+
+/// A problem with the slot's progress as a whole, rather than with a single value.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Finding {
+    pub problem: Problem,
+    pub fix: Option<Fix>,
+}
+
+// `GoalNormal | GoalSecret` rather than the `Debug` form
+fn flag_names<F: bitflags::Flags>(flags: F) -> String {
+    flags
+        .iter_names()
+        .map(|(name, _)| name)
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
+
+fn has(flags: u32, flag: StageCompletionFlags) -> bool {
+    flags & flag.bits() != 0
+}
+
+struct Checker<'a> {
+    slot: &'a SaveSlot,
+    index: usize,
+    out: Vec<Finding>,
+}
+
+impl Checker<'_> {
+    fn push(
+        &mut self,
+        severity: Severity,
+        field: &'static str,
+        index: &[usize],
+        message: String,
+        fix: Option<Fix>,
+    ) {
+        self.out.push(Finding {
+            problem: Problem::new(severity, Some(self.index), field, index, message),
+            fix,
+        });
+    }
+
+    fn game_flag(&self, flag: GameCompletionFlags) -> bool {
+        self.slot.game_completion_flags & flag.bits() != 0
+    }
+
+    fn goal(&self, world: usize, stage: usize) -> bool {
+        has(
+            self.slot.stage_completion_flags[world][stage],
+            StageCompletionFlags::GoalNormal,
+        )
+    }
+
+    // the game lets you skip worlds with warp cannons, so any cleared cannon before
+    // `world` counts as a way in
+    fn reachable(&self, world: usize) -> bool {
+        let previous = world - 1;
+        let castle_cleared = stages::world_castle(previous)
+            .map(|c| self.goal(previous, c))
+            .unwrap_or(false);
+
+        castle_cleared || (0..world).any(|w| self.goal(w, CANNON))
+    }
+
+    fn check_worlds(&mut self) {
+        let unlocked = self.slot.world_unlocked;
+
+        for (w, &is_unlocked) in unlocked.iter().enumerate().take(SPECIAL_WORLD).skip(1) {
+            if is_unlocked && !self.reachable(w) {
+                self.push(
+                    Severity::Warning,
+                    "world_unlocked",
+                    &[w],
+                    format!(
+                        "World {} is unlocked, but World {}'s castle hasn't been cleared",
+                        w + 1,
+                        w
+                    ),
+                    Some(Fix::LockWorld(w)),
+                );
+            }
+        }
+
+        if unlocked[SPECIAL_WORLD] && !self.game_flag(GameCompletionFlags::FinalBossBeaten) {
+            self.push(
+                Severity::Warning,
+                "world_unlocked",
+                &[SPECIAL_WORLD],
+                format!(
+                    "World {} is unlocked, but the final boss hasn't been beaten",
+                    SPECIAL_WORLD + 1
+                ),
+                Some(Fix::LockWorld(SPECIAL_WORLD)),
+            );
+        }
+
+        let cur = self.slot.cur_world as usize;
+
+        // an out-of-range world is already reported by the validation pass
+        if cur < ACTUAL_WORLD_COUNT && !unlocked[cur] {
+            let fix = (0..ACTUAL_WORLD_COUNT)
+                .rev()
+                .find(|&w| unlocked[w])
+                .map(Fix::SetCurrentWorld);
+
+            self.push(
+                Severity::Error,
+                "cur_world",
+                &[],
+                format!("World {} is current, but it isn't unlocked", cur + 1),
+                fix,
+            );
+        }
+    }
+
+    fn check_game_flags(&mut self) {
+        let earned = self.slot.derived_completion_flags();
+
+        let reasons = [
+            (
+                GameCompletionFlags::AllStarCoinsReg,
+                format!("some star coins in Worlds 1-{} are missing", SPECIAL_WORLD),
+            ),
+            (
+                GameCompletionFlags::AllStarCoinsSpe,
+                format!("some star coins in World {} are missing", SPECIAL_WORLD + 1),
+            ),
+            (
+                GameCompletionFlags::AllGoals,
+                String::from("some goals haven't been reached"),
+            ),
+            (
+                GameCompletionFlags::GameCompleted,
+                String::from("not everything has been done"),
+            ),
+        ];
+
+        for (flag, reason) in reasons {
+            if self.game_flag(flag) && !earned.contains(flag) {
+                self.push(
+                    Severity::Warning,
+                    "game_completion_flags",
+                    &[],
+                    format!("{} is set, but {}", flag_names(flag), reason),
+                    Some(Fix::ClearGameFlags(flag)),
+                );
+            }
+        }
+    }
+
+    fn check_stages(&mut self) {
+        let pairs = [
+            (
+                StageCompletionFlags::SuperGuideGoalNormal,
+                StageCompletionFlags::GoalNormal,
+            ),
+            (
+                StageCompletionFlags::SuperGuideGoalSecret,
+                StageCompletionFlags::GoalSecret,
+            ),
+        ];
+
+        for w in 0..WORLD_COUNT {
+            for st in 0..STAGE_COUNT {
+                let flags = self.slot.stage_completion_flags[w][st];
+
+                if flags == 0 {
+                    continue;
+                }
+
+                let Some(course) = stages::find_course(w, st) else {
+                    // toad houses, cannons and the like keep flags of their own, so only
+                    // course slots that don't exist in this world are suspicious. The
+                    // stage tables aren't read from the game, so the flags are kept.
+                    if stages::is_course_index(st) {
+                        self.push(
+                            Severity::Warning,
+                            "stage_completion_flags",
+                            &[w, st],
+                            format!(
+                                "{} has flags set, but there is no such stage",
+                                stage_name(w, st)
+                            ),
+                            None,
+                        );
+                    }
+
+                    continue;
+                };
+
+                for (guide, goal) in pairs {
+                    if has(flags, guide) && !has(flags, goal) {
+                        self.push(
+                            Severity::Warning,
+                            "stage_completion_flags",
+                            &[w, st],
+                            format!("{} is set without {}", flag_names(guide), flag_names(goal)),
+                            Some(Fix::SetStageFlags(w, st, goal)),
+                        );
+                    }
+                }
+
+                if !course.secret_exit && has(flags, StageCompletionFlags::GoalSecret) {
+                    self.push(
+                        Severity::Warning,
+                        "stage_completion_flags",
+                        &[w, st],
+                        format!(
+                            "{} has no secret exit, but GoalSecret is set",
+                            stage_name(w, st)
+                        ),
+                        None,
+                    );
+                }
+            }
+        }
+    }
+
+    fn check_hint_movies(&mut self) {
+        for m in 0..ACTUAL_HINT_MOVIE_COUNT {
+            if !self.slot.hint_movie_bought[m] {
+                continue;
+            }
+
+            let Some(w) = stages::hint_movie_world(m) else {
+                continue;
+            };
+
+            if !self.slot.world_unlocked[w] {
+                self.push(
+                    Severity::Warning,
+                    "hint_movie_bought",
+                    &[m],
+                    format!("is bought, but World {} hasn't been reached", w + 1),
+                    Some(Fix::UnbuyHintMovie(m)),
+                );
+            }
+        }
+    }
+}
+
+/// The completion flags that follow from the stage flags rather than being set by an event.
+pub const DERIVED_COMPLETION_FLAGS: GameCompletionFlags = GameCompletionFlags::AllGoals
+    .union(GameCompletionFlags::AllStarCoinsReg)
+    .union(GameCompletionFlags::AllStarCoinsSpe)
+    .union(GameCompletionFlags::GameCompleted);
+
+impl SaveSlot {
+    /// What [`DERIVED_COMPLETION_FLAGS`] should be, going by the stage flags.
+    ///
+    /// `GameCompleted` is taken to mean the final boss is beaten and every other derived
+    /// flag is earned. That rule hasn't been checked against a real save yet;
+    /// `tests/completion_flags.rs` pins it down so a change to it is deliberate.
+    pub fn derived_completion_flags(&self) -> GameCompletionFlags {
+        let flags = &self.stage_completion_flags;
+        let regular: Vec<usize> = (0..SPECIAL_WORLD).collect();
+
+        let mut derived = GameCompletionFlags::empty();
+
+        derived.set(GameCompletionFlags::AllGoals, stages::all_goals(flags));
+        derived.set(
+            GameCompletionFlags::AllStarCoinsReg,
+            stages::all_star_coins(flags, &regular),
+        );
+        derived.set(
+            GameCompletionFlags::AllStarCoinsSpe,
+            stages::all_star_coins(flags, &[SPECIAL_WORLD]),
+        );
+
+        let beaten = self.game_completion_flags & GameCompletionFlags::FinalBossBeaten.bits() != 0;
+        let everything = GameCompletionFlags::AllGoals
+            | GameCompletionFlags::AllStarCoinsReg
+            | GameCompletionFlags::AllStarCoinsSpe;
+
+        derived.set(
+            GameCompletionFlags::GameCompleted,
+            beaten && derived.contains(everything),
+        );
+
+        derived
+    }
+
+    /// Replaces the [`DERIVED_COMPLETION_FLAGS`] with what the stage flags say, leaving
+    /// the other bits alone. Returns whether anything changed.
+    pub fn sync_completion_flags(&mut self) -> bool {
+        let old = self.game_completion_flags;
+
+        self.game_completion_flags =
+            (old & !DERIVED_COMPLETION_FLAGS.bits()) | self.derived_completion_flags().bits();
+
+        self.game_completion_flags != old
+    }
+
+    /// Checks the slot's progress for combinations the game can't reach, such as a
+    /// world unlocked before the one leading to it was finished. `index` is only used
+    /// to fill in [`Problem::slot`].
+    pub fn check_consistency(&self, index: usize) -> Vec<Finding> {
+        let mut checker = Checker {
+            slot: self,
+            index,
+            out: Vec::new(),
+        };
+
+        checker.check_worlds();
+        checker.check_game_flags();
+        checker.check_stages();
+        checker.check_hint_movies();
+
+        checker.out
+    }
+}
+
+impl SaveFile {
+    /// [`SaveSlot::sync_completion_flags`] for every slot that isn't empty. Returns the
+    /// number of slots that changed.
+    pub fn sync_completion_flags(&mut self) -> usize {
+        self.save_slots
+            .iter_mut()
+            .filter(|slot| !slot.is_empty())
+            .map(|slot| slot.sync_completion_flags())
+            .filter(|&changed| changed)
+            .count()
+    }
+
+    /// [`SaveSlot::check_consistency`] for every slot that isn't empty.
+    pub fn check_consistency(&self) -> Vec<Finding> {
+        self.save_slots
+            .iter()
+            .enumerate()
+            .filter(|(_, slot)| !slot.is_empty())
+            .flat_map(|(i, slot)| slot.check_consistency(i))
+            .collect()
+    }
+
+    /// Applies the fix of `finding`, if it has one. Returns whether anything was done.
+    pub fn apply_fix(&mut self, finding: &Finding) -> bool {
+        match (finding.problem.slot, finding.fix) {
+            (Some(i), Some(fix)) if i < SAVE_SLOT_COUNT => {
+                fix.apply(&mut self.save_slots[i]);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Applies fixes until no fixable findings are left, since one fix can expose
+    /// another problem (locking a world can orphan its hint movies). Returns the
+    /// number of fixes applied.
+    pub fn fix_consistency(&mut self) -> usize {
+        let mut applied = 0;
+
+        loop {
+            let before = self.clone();
+
+            for finding in &self.check_consistency() {
+                if self.apply_fix(finding) {
+                    applied += 1;
+                }
+            }
+
+            if *self == before {
+                return applied;
+            }
+        }
+    }
+}
@@ -0,0 +1,146 @@
+use json::{object, JsonValue};
+
+use crate::savefile::constants::*;
+use crate::savefile::fields::Field;
+use crate::savefile::stages::stage_name;
+use crate::savefile::SaveFile;
+
+/// A single difference between two save files.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Change {
+    /// The logical path, such as `slot[1].stage[3][21].GoalNormal`.
+    pub path: String,
+    /// What the path refers to in the game, such as `4-Tower`. May be empty.
+    pub label: String,
+    pub old: String,
+    pub new: String,
+}
+
+/// Describes what an indexed field refers to.
+pub fn field_label(name: &str, index: &[usize]) -> String {
+    match (name, index) {
+        (
+            "stage_completion_flags"
+            | "player_death_count"
+            | "free_mode_play_count"
+            | "coin_battle_play_count",
+            [w, s],
+        ) => stage_name(*w, *s),
+
+        (
+            "enemy_revival_count" | "enemy_subworld" | "enemy_pos_index" | "enemy_walk_direction",
+            [w, e],
+        ) => format!("World {}, ambush enemy {}", w + 1, e + 1),
+
+        ("starting_mushroom_house_type" | "world_unlocked" | "toad_rescue_level", [w]) => {
+            format!("World {}", w + 1)
+        }
+
+        ("item_stock", [i]) => ITEM_MENU_POWERUP_NAMES[*i].to_string(),
+
+        ("hint_movie_bought", [i]) => match HINT_MOVIE_TITLES.get(*i) {
+            Some(title) => title.to_string(),
+            None => format!("(unused hint movie {})", i),
+        },
+
+        (name, [p]) if name.starts_with("player_") => format!("Player {}", p + 1),
+
+        _ => String::new(),
+    }
+}
+
+// stage flags are the most common change, so they get a shorter path
+fn change_path(field: &Field) -> String {
+    field
+        .path
+        .replacen(".stage_completion_flags[", ".stage[", 1)
+}
+
+fn compare(old: &[Field], new: &[Field], out: &mut Vec<Change>) {
+    for (a, b) in old.iter().zip(new.iter()) {
+        if a.value == b.value {
+            continue;
+        }
+
+        let path = change_path(a);
+        let label = field_label(a.name, &a.index);
+
+        let Some(table) = a.kind.flag_table() else {
+            out.push(Change {
+                path,
+                label,
+                old: a.display_value(),
+                new: b.display_value(),
+            });
+
+            continue;
+        };
+
+        // flag words are reported one bit at a time
+        let changed = a.value ^ b.value;
+        let known = table.iter().fold(0, |acc, (_, bit)| acc | bit);
+
+        let unknown = (0..32)
+            .map(|i| 1u32 << i)
+            .filter(|bit| changed & !known & bit != 0)
+            .map(|bit| (format!("{:#X}", bit), bit));
+
+        let bits = table
+            .iter()
+            .filter(|(_, bit)| changed & bit != 0)
+            .map(|(name, bit)| (name.to_string(), *bit))
+            .chain(unknown);
+
+        for (name, bit) in bits {
+            out.push(Change {
+                path: format!("{}.{}", path, name),
+                label: label.clone(),
+                old: (a.value & bit != 0).to_string(),
+                new: (b.value & bit != 0).to_string(),
+            });
+        }
+    }
+}
+
+/// Every field that differs between `old` and `new`, in file order.
+pub fn diff(old: &SaveFile, new: &SaveFile) -> Vec<Change> {
+    let mut out = Vec::new();
+
+    compare(&old.header_fields(), &new.header_fields(), &mut out);
+
+    for i in 0..SAVE_SLOT_COUNT {
+        compare(&old.slot_fields(i), &new.slot_fields(i), &mut out);
+    }
+
+    out
+}
+
+/// One change per line, e.g. `slot[1].stage[3][21].GoalNormal: false -> true (World 4, Tower 1)`.
+pub fn render_text(changes: &[Change]) -> String {
+    changes
+        .iter()
+        .map(|c| {
+            if c.label.is_empty() {
+                format!("{}: {} -> {}", c.path, c.old, c.new)
+            } else {
+                format!("{}: {} -> {} ({})", c.path, c.old, c.new, c.label)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub fn render_json(changes: &[Change]) -> JsonValue {
+    changes
+        .iter()
+        .map(|c| {
+            object! {
+                path: c.path.clone(),
+                label: c.label.clone(),
+                old: c.old.clone(),
+                new: c.new.clone(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .into()
+}
@@ -0,0 +1,90 @@
+use std::fmt;
+use std::io;
+
+/// Everything that can go wrong while reading a save file.
+///
+/// Offsets are absolute positions in the file. `slot` is `None` for errors in the header.
+#[derive(Debug)]
+pub enum SaveError {
+    Io(io::Error),
+    TooShort {
+        expected: usize,
+        actual: usize,
+    },
+    BadMagic {
+        found: [u8; 3],
+    },
+    UnsupportedVersion {
+        version: u8,
+        offset: usize,
+        slot: Option<usize>,
+    },
+    OutOfRange {
+        field: &'static str,
+        value: u32,
+        offset: usize,
+        slot: Option<usize>,
+    },
+}
+
+fn location(offset: usize, slot: Option<usize>) -> String {
+    match slot {
+        Some(s) => format!("slot {} at offset {:#X}", s, offset),
+        None => format!("header at offset {:#X}", offset),
+    }
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Io(e) => write!(f, "could not read file: {}", e),
+            SaveError::TooShort { expected, actual } => write!(
+                f,
+                "file is too short: expected {:#X} bytes, got {:#X}",
+                expected, actual
+            ),
+            SaveError::BadMagic { found } => write!(
+                f,
+                "not a New Super Mario Bros. Wii save file (magic is {:?})",
+                String::from_utf8_lossy(found)
+            ),
+            SaveError::UnsupportedVersion {
+                version,
+                offset,
+                slot,
+            } => write!(
+                f,
+                "unsupported save version {:#X} in {}",
+                version,
+                location(*offset, *slot)
+            ),
+            SaveError::OutOfRange {
+                field,
+                value,
+                offset,
+                slot,
+            } => write!(
+                f,
+                "value {} is out of range for {} in {}",
+                value,
+                field,
+                location(*offset, *slot)
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SaveError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for SaveError {
+    fn from(e: io::Error) -> Self {
+        SaveError::Io(e)
+    }
+}
@@ -1,14 +1,20 @@
 use byteorder::{BigEndian, ByteOrder};
-use crc32fast as crc32;
 
+use crate::savefile::checksum::{header_crc, HEADER_CRC_OFFSET};
 use crate::savefile::constants::*;
+use crate::savefile::saveerror::SaveError;
 
+#[derive(Clone, PartialEq)]
 pub struct SaveHeader {
     pub region: SaveFileRegion,
     pub last_selected_index: u8,
     pub free_mode_play_count: [[u16; STAGE_COUNT]; WORLD_COUNT],
     pub coin_battle_play_count: [[u16; STAGE_COUNT]; WORLD_COUNT],
     pub extra_modes_unlocked_worlds: u16, // flags for each world
+
+    // the header as it was read. edits are written over it so that
+    // the version and any unmapped bytes survive a save
+    raw: Vec<u8>,
 }
 
 impl SaveHeader {
@@ -19,24 +25,48 @@ impl SaveHeader {
             free_mode_play_count: [[0; STAGE_COUNT]; WORLD_COUNT],
             coin_battle_play_count: [[0; STAGE_COUNT]; WORLD_COUNT],
             extra_modes_unlocked_worlds: 0,
+            raw: {
+                let mut raw = vec![0u8; HEADER_SIZE];
+                raw[0x4] = SAVE_VERSION;
+                raw
+            },
         }
     }
 
-    pub fn from_bytes(input: &[u8]) -> Self {
-        let region = match &input[3] {
-            b'E' => SaveFileRegion::NTSC,
-            b'P' => SaveFileRegion::PAL,
-            b'J' => SaveFileRegion::JPN,
-            b'K' => SaveFileRegion::KOR,
-            b'C' => SaveFileRegion::CHN,
-            b'W' => SaveFileRegion::TW,
-
-            _ => {
-                // default to NTSC
-                SaveFileRegion::NTSC
+    pub fn from_bytes(input: &[u8]) -> Result<Self, SaveError> {
+        if input.len() < HEADER_SIZE {
+            return Err(SaveError::TooShort {
+                expected: HEADER_SIZE,
+                actual: input.len(),
+            });
+        }
+
+        if &input[..3] != b"SMN" {
+            return Err(SaveError::BadMagic {
+                found: [input[0], input[1], input[2]],
+            });
+        }
+
+        let region = match SaveFileRegion::from_code(input[3]) {
+            Some(r) => r,
+            None => {
+                return Err(SaveError::OutOfRange {
+                    field: "region",
+                    value: input[3] as u32,
+                    offset: 3,
+                    slot: None,
+                });
             }
         };
 
+        if input[0x4] != SAVE_VERSION {
+            return Err(SaveError::UnsupportedVersion {
+                version: input[0x4],
+                offset: 0x4,
+                slot: None,
+            });
+        }
+
         let last_selected_index = input[0x6];
 
         let mut free_mode_play_count: [[u16; STAGE_COUNT]; WORLD_COUNT] =
@@ -63,33 +93,24 @@ impl SaveHeader {
 
         let extra_modes_unlocked_worlds = BigEndian::read_u16(&input[0x698..0x69A]);
 
-        Self {
+        Ok(Self {
             region,
             last_selected_index,
             free_mode_play_count,
             coin_battle_play_count,
             extra_modes_unlocked_worlds,
-        }
+            raw: input[..HEADER_SIZE].to_vec(),
+        })
     }
 
     pub fn to_bytes(&self) -> Vec<u8> {
-        let mut out = [0u8; HEADER_SIZE];
+        let mut out = self.raw.clone();
 
         // magic
         out[0] = b'S';
         out[1] = b'M';
         out[2] = b'N';
-        out[3] = match self.region {
-            SaveFileRegion::NTSC => b'E',
-            SaveFileRegion::PAL => b'P',
-            SaveFileRegion::JPN => b'J',
-            SaveFileRegion::KOR => b'K',
-            SaveFileRegion::CHN => b'C',
-            SaveFileRegion::TW => b'W',
-        };
-
-        // version - 0x0E00.
-        out[0x4] = 0xE;
+        out[3] = self.region.code();
 
         // last selected save file
         out[0x6] = self.last_selected_index;
@@ -116,10 +137,10 @@ impl SaveHeader {
         BigEndian::write_u16(&mut out[0x698..0x69A], self.extra_modes_unlocked_worlds);
 
         // crc32 is calculated excluding the magic
-        let crc = crc32::hash(&out[4..0x69C]);
+        let crc = header_crc(&out);
 
-        BigEndian::write_u32(&mut out[0x69C..0x6A0], crc);
+        BigEndian::write_u32(&mut out[HEADER_CRC_OFFSET..HEADER_SIZE], crc);
 
-        out.to_vec()
+        out
     }
 }
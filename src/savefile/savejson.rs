@@ -0,0 +1,613 @@
+use json::{self, object, JsonValue};
+use std::fmt;
+
+use crate::savefile::constants::*;
+use crate::savefile::fields::flag_table;
+use crate::savefile::saveheader::SaveHeader;
+use crate::savefile::saveslot::SaveSlot;
+use crate::savefile::SaveFile;
+
+// the json document is described by `schema/save.schema.json`.
+// bump JSON_FORMAT_VERSION whenever a change would break existing documents.
+
+pub const JSON_FORMAT_NAME: &str = "penguin-save";
+pub const JSON_FORMAT_VERSION: u32 = 1;
+pub const JSON_SCHEMA: &str = include_str!("../../schema/save.schema.json");
+
+#[derive(Debug)]
+pub enum JsonError {
+    Parse(json::Error),
+    UnsupportedVersion(u32),
+    Invalid { path: String, message: String },
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonError::Parse(e) => write!(f, "invalid json: {}", e),
+            JsonError::UnsupportedVersion(v) => write!(
+                f,
+                "unsupported format version {} (expected at most {})",
+                v, JSON_FORMAT_VERSION
+            ),
+            JsonError::Invalid { path, message } => write!(f, "{}: {}", path, message),
+        }
+    }
+}
+
+impl std::error::Error for JsonError {}
+
+fn invalid(path: &str, message: &str) -> JsonError {
+    JsonError::Invalid {
+        path: path.to_string(),
+        message: message.to_string(),
+    }
+}
+
+// writing
+
+fn enum_to_json<E: Copy + Into<u8> + fmt::Debug>(value: E, count: u8) -> JsonValue {
+    let code: u8 = value.into();
+
+    // unknown values are kept as plain numbers
+    if code < count {
+        format!("{:?}", value).into()
+    } else {
+        code.into()
+    }
+}
+
+fn flags_to_json(value: u32, table: &[(&'static str, u32)]) -> JsonValue {
+    let mut out = JsonValue::new_array();
+    let mut rest = value;
+
+    for (name, bit) in table {
+        if value & bit != 0 {
+            let _ = out.push(*name);
+            rest &= !bit;
+        }
+    }
+
+    // undocumented bits are kept as plain numbers
+    for i in 0..32 {
+        if rest & (1 << i) != 0 {
+            let _ = out.push(1u32 << i);
+        }
+    }
+
+    out
+}
+
+fn matrix_to_json<T: Copy + Into<JsonValue>, const N: usize>(rows: &[[T; N]]) -> JsonValue {
+    rows.iter()
+        .map(|r| r.iter().map(|v| (*v).into()).collect::<Vec<JsonValue>>())
+        .collect::<Vec<_>>()
+        .into()
+}
+
+fn header_to_json(header: &SaveHeader) -> JsonValue {
+    object! {
+        region: format!("{:?}", header.region),
+        last_selected_index: header.last_selected_index,
+        extra_modes_unlocked_worlds: header.extra_modes_unlocked_worlds,
+        free_mode_play_count: matrix_to_json(&header.free_mode_play_count),
+        coin_battle_play_count: matrix_to_json(&header.coin_battle_play_count),
+    }
+}
+
+fn slot_to_json(slot: &SaveSlot) -> JsonValue {
+    let game_flags = flag_table::<GameCompletionFlags>();
+    let spawn_flags = flag_table::<PlayerCreationFlags>();
+    let stage_flags = flag_table::<StageCompletionFlags>();
+
+    let players: Vec<JsonValue> = (0..PLAYER_COUNT)
+        .map(|i| {
+            object! {
+                character: enum_to_json(slot.player_character[i], 4),
+                powerup: enum_to_json(slot.player_powerup[i], 7),
+                lives: slot.player_lives[i],
+                coins: slot.player_coins[i],
+                continues: slot.player_continues[i],
+                spawn_flags: flags_to_json(slot.player_spawn_flags[i] as u32, &spawn_flags),
+            }
+        })
+        .collect();
+
+    let ambush_enemies: Vec<JsonValue> = (0..WORLD_COUNT)
+        .map(|w| {
+            (0..AMBUSH_ENEMY_COUNT)
+                .map(|e| {
+                    object! {
+                        revival_count: slot.enemy_revival_count[w][e],
+                        subworld: slot.enemy_subworld[w][e],
+                        pos_index: slot.enemy_pos_index[w][e],
+                        walk_direction: enum_to_json(slot.enemy_walk_direction[w][e], 3),
+                    }
+                })
+                .collect::<Vec<_>>()
+                .into()
+        })
+        .collect();
+
+    let stage_completion_flags: Vec<JsonValue> = slot
+        .stage_completion_flags
+        .iter()
+        .map(|world| {
+            world
+                .iter()
+                .map(|f| flags_to_json(*f, &stage_flags))
+                .collect::<Vec<_>>()
+                .into()
+        })
+        .collect();
+
+    object! {
+        game_completion_flags: flags_to_json(slot.game_completion_flags as u32, &game_flags),
+        cur_world: slot.cur_world,
+        cur_subworld: slot.cur_subworld,
+        cur_path_node: slot.cur_path_node,
+        w5_vine_reshuffle_counter: slot.w5_vine_reshuffle_counter,
+        w3_switch_on: slot.w3_switch_on,
+        ingame_score: slot.ingame_score,
+        staff_credits_high_score: slot.staff_credits_high_score,
+        item_stock: slot.item_stock.to_vec(),
+        players: players,
+        world_unlocked: slot.world_unlocked.to_vec(),
+        starting_mushroom_house_type: slot
+            .starting_mushroom_house_type
+            .iter()
+            .map(|h| enum_to_json(*h, 7))
+            .collect::<Vec<_>>(),
+        toad_rescue_level: slot.toad_rescue_level.to_vec(),
+        stage_completion_flags: stage_completion_flags,
+        hint_movie_bought: slot.hint_movie_bought.to_vec(),
+        ambush_enemies: ambush_enemies,
+        player_death_count: matrix_to_json(&slot.player_death_count),
+        player_death_count_w3_l4_switch: slot.player_death_count_w3_l4_switch,
+    }
+}
+
+fn is_scalar(v: &JsonValue) -> bool {
+    !v.is_array() && !v.is_object()
+}
+
+fn write_pretty(v: &JsonValue, indent: usize, out: &mut String) {
+    let pad = "  ".repeat(indent + 1);
+
+    if v.is_object() && !v.is_empty() {
+        out.push_str("{\n");
+
+        for (i, (key, value)) in v.entries().enumerate() {
+            if i > 0 {
+                out.push_str(",\n");
+            }
+
+            out.push_str(&format!("{}{}: ", pad, json::stringify(key)));
+            write_pretty(value, indent + 1, out);
+        }
+
+        out.push_str(&format!("\n{}}}", "  ".repeat(indent)));
+    } else if v.is_array() && !v.members().all(is_scalar) {
+        out.push_str("[\n");
+
+        for (i, member) in v.members().enumerate() {
+            if i > 0 {
+                out.push_str(",\n");
+            }
+
+            out.push_str(&pad);
+            write_pretty(member, indent + 1, out);
+        }
+
+        out.push_str(&format!("\n{}]", "  ".repeat(indent)));
+    } else if v.is_array() {
+        // lists of scalars go on a single line
+        let members: Vec<String> = v.members().map(|m| json::stringify(m.clone())).collect();
+        out.push_str(&format!("[{}]", members.join(", ")));
+    } else {
+        out.push_str(&json::stringify(v.clone()));
+    }
+}
+
+// reading. missing keys keep the value of a blank save, but anything
+// that is present has to have the right type and range.
+
+fn read_number(v: &JsonValue, path: &str, max: u32) -> Result<u32, JsonError> {
+    match v.as_u32() {
+        Some(n) if n <= max => Ok(n),
+        Some(_) => Err(invalid(path, &format!("must be at most {}", max))),
+        None => Err(invalid(path, "expected a non-negative integer")),
+    }
+}
+
+fn read_u8(v: &JsonValue, path: &str, out: &mut u8) -> Result<(), JsonError> {
+    if !v.is_null() {
+        *out = read_number(v, path, u8::MAX as u32)? as u8;
+    }
+
+    Ok(())
+}
+
+fn read_u16(v: &JsonValue, path: &str, out: &mut u16) -> Result<(), JsonError> {
+    if !v.is_null() {
+        *out = read_number(v, path, u16::MAX as u32)? as u16;
+    }
+
+    Ok(())
+}
+
+fn read_u32(v: &JsonValue, path: &str, out: &mut u32) -> Result<(), JsonError> {
+    if !v.is_null() {
+        *out = read_number(v, path, u32::MAX)?;
+    }
+
+    Ok(())
+}
+
+fn read_bool(v: &JsonValue, path: &str, out: &mut bool) -> Result<(), JsonError> {
+    if !v.is_null() {
+        *out = v
+            .as_bool()
+            .ok_or_else(|| invalid(path, "expected true or false"))?;
+    }
+
+    Ok(())
+}
+
+fn read_enum<E: From<u8> + fmt::Debug>(
+    v: &JsonValue,
+    path: &str,
+    count: u8,
+    out: &mut E,
+) -> Result<(), JsonError> {
+    if v.is_null() {
+        return Ok(());
+    }
+
+    if let Some(name) = v.as_str() {
+        *out = (0..count)
+            .map(E::from)
+            .find(|e| format!("{:?}", e) == name)
+            .ok_or_else(|| invalid(path, &format!("unknown name {:?}", name)))?;
+    } else {
+        *out = E::from(read_number(v, path, u8::MAX as u32)? as u8);
+    }
+
+    Ok(())
+}
+
+fn read_flags(
+    v: &JsonValue,
+    path: &str,
+    table: &[(&'static str, u32)],
+    max: u32,
+) -> Result<Option<u32>, JsonError> {
+    if v.is_null() {
+        return Ok(None);
+    }
+
+    if !v.is_array() {
+        return Err(invalid(path, "expected a list of flag names"));
+    }
+
+    let mut value = 0;
+
+    for (i, member) in v.members().enumerate() {
+        let path = format!("{}[{}]", path, i);
+
+        value |= match member.as_str() {
+            Some(name) => table
+                .iter()
+                .find(|(n, _)| *n == name)
+                .map(|(_, bit)| *bit)
+                .ok_or_else(|| invalid(&path, &format!("unknown flag {:?}", name)))?,
+            None => read_number(member, &path, max)?,
+        };
+    }
+
+    Ok(Some(value))
+}
+
+/// Calls `read` for every member of a fixed-length array.
+fn read_array<F>(v: &JsonValue, path: &str, len: usize, mut read: F) -> Result<(), JsonError>
+where
+    F: FnMut(&JsonValue, &str, usize) -> Result<(), JsonError>,
+{
+    if v.is_null() {
+        return Ok(());
+    }
+
+    if !v.is_array() || v.len() != len {
+        return Err(invalid(path, &format!("expected a list of {} items", len)));
+    }
+
+    for (i, member) in v.members().enumerate() {
+        read(member, &format!("{}[{}]", path, i), i)?;
+    }
+
+    Ok(())
+}
+
+fn read_header(v: &JsonValue, path: &str) -> Result<SaveHeader, JsonError> {
+    let mut header = SaveHeader::blank();
+
+    if let Some(name) = v["region"].as_str() {
+        header.region = SaveFileRegion::ALL
+            .into_iter()
+            .find(|r| format!("{:?}", r) == name)
+            .ok_or_else(|| invalid(&format!("{}.region", path), "unknown region"))?;
+    } else if !v["region"].is_null() {
+        return Err(invalid(&format!("{}.region", path), "expected a string"));
+    }
+
+    read_u8(
+        &v["last_selected_index"],
+        &format!("{}.last_selected_index", path),
+        &mut header.last_selected_index,
+    )?;
+    read_u16(
+        &v["extra_modes_unlocked_worlds"],
+        &format!("{}.extra_modes_unlocked_worlds", path),
+        &mut header.extra_modes_unlocked_worlds,
+    )?;
+
+    for (key, counts) in [
+        ("free_mode_play_count", &mut header.free_mode_play_count),
+        ("coin_battle_play_count", &mut header.coin_battle_play_count),
+    ] {
+        read_array(
+            &v[key],
+            &format!("{}.{}", path, key),
+            WORLD_COUNT,
+            |v, p, w| {
+                read_array(v, p, STAGE_COUNT, |v, p, s| {
+                    read_u16(v, p, &mut counts[w][s])
+                })
+            },
+        )?;
+    }
+
+    Ok(header)
+}
+
+fn read_slot(v: &JsonValue, path: &str) -> Result<SaveSlot, JsonError> {
+    let mut slot = SaveSlot::blank();
+    let p = |key: &str| format!("{}.{}", path, key);
+
+    if let Some(f) = read_flags(
+        &v["game_completion_flags"],
+        &p("game_completion_flags"),
+        &flag_table::<GameCompletionFlags>(),
+        u8::MAX as u32,
+    )? {
+        slot.game_completion_flags = f as u8;
+    }
+
+    read_u8(&v["cur_world"], &p("cur_world"), &mut slot.cur_world)?;
+    read_u8(
+        &v["cur_subworld"],
+        &p("cur_subworld"),
+        &mut slot.cur_subworld,
+    )?;
+    read_u8(
+        &v["cur_path_node"],
+        &p("cur_path_node"),
+        &mut slot.cur_path_node,
+    )?;
+    read_u8(
+        &v["w5_vine_reshuffle_counter"],
+        &p("w5_vine_reshuffle_counter"),
+        &mut slot.w5_vine_reshuffle_counter,
+    )?;
+    read_bool(
+        &v["w3_switch_on"],
+        &p("w3_switch_on"),
+        &mut slot.w3_switch_on,
+    )?;
+    read_u32(
+        &v["ingame_score"],
+        &p("ingame_score"),
+        &mut slot.ingame_score,
+    )?;
+    read_u16(
+        &v["staff_credits_high_score"],
+        &p("staff_credits_high_score"),
+        &mut slot.staff_credits_high_score,
+    )?;
+
+    read_array(
+        &v["item_stock"],
+        &p("item_stock"),
+        POWERUP_COUNT,
+        |v, p, i| read_u8(v, p, &mut slot.item_stock[i]),
+    )?;
+
+    read_array(&v["players"], &p("players"), PLAYER_COUNT, |v, p, i| {
+        read_enum(
+            &v["character"],
+            &format!("{}.character", p),
+            4,
+            &mut slot.player_character[i],
+        )?;
+        read_enum(
+            &v["powerup"],
+            &format!("{}.powerup", p),
+            7,
+            &mut slot.player_powerup[i],
+        )?;
+        read_u8(
+            &v["lives"],
+            &format!("{}.lives", p),
+            &mut slot.player_lives[i],
+        )?;
+        read_u8(
+            &v["coins"],
+            &format!("{}.coins", p),
+            &mut slot.player_coins[i],
+        )?;
+        read_u8(
+            &v["continues"],
+            &format!("{}.continues", p),
+            &mut slot.player_continues[i],
+        )?;
+
+        if let Some(f) = read_flags(
+            &v["spawn_flags"],
+            &format!("{}.spawn_flags", p),
+            &flag_table::<PlayerCreationFlags>(),
+            u8::MAX as u32,
+        )? {
+            slot.player_spawn_flags[i] = f as u8;
+        }
+
+        Ok(())
+    })?;
+
+    read_array(
+        &v["world_unlocked"],
+        &p("world_unlocked"),
+        WORLD_COUNT,
+        |v, p, i| read_bool(v, p, &mut slot.world_unlocked[i]),
+    )?;
+
+    read_array(
+        &v["starting_mushroom_house_type"],
+        &p("starting_mushroom_house_type"),
+        WORLD_COUNT,
+        |v, p, i| read_enum(v, p, 7, &mut slot.starting_mushroom_house_type[i]),
+    )?;
+
+    read_array(
+        &v["toad_rescue_level"],
+        &p("toad_rescue_level"),
+        WORLD_COUNT,
+        |v, p, i| read_u8(v, p, &mut slot.toad_rescue_level[i]),
+    )?;
+
+    let stage_flags = flag_table::<StageCompletionFlags>();
+    read_array(
+        &v["stage_completion_flags"],
+        &p("stage_completion_flags"),
+        WORLD_COUNT,
+        |v, p, w| {
+            read_array(v, p, STAGE_COUNT, |v, p, s| {
+                if let Some(f) = read_flags(v, p, &stage_flags, u32::MAX)? {
+                    slot.stage_completion_flags[w][s] = f;
+                }
+
+                Ok(())
+            })
+        },
+    )?;
+
+    read_array(
+        &v["hint_movie_bought"],
+        &p("hint_movie_bought"),
+        HINT_MOVIE_COUNT,
+        |v, p, i| read_bool(v, p, &mut slot.hint_movie_bought[i]),
+    )?;
+
+    read_array(
+        &v["ambush_enemies"],
+        &p("ambush_enemies"),
+        WORLD_COUNT,
+        |v, p, w| {
+            read_array(v, p, AMBUSH_ENEMY_COUNT, |v, p, e| {
+                read_u8(
+                    &v["revival_count"],
+                    &format!("{}.revival_count", p),
+                    &mut slot.enemy_revival_count[w][e],
+                )?;
+                read_u8(
+                    &v["subworld"],
+                    &format!("{}.subworld", p),
+                    &mut slot.enemy_subworld[w][e],
+                )?;
+                read_u8(
+                    &v["pos_index"],
+                    &format!("{}.pos_index", p),
+                    &mut slot.enemy_pos_index[w][e],
+                )?;
+                read_enum(
+                    &v["walk_direction"],
+                    &format!("{}.walk_direction", p),
+                    3,
+                    &mut slot.enemy_walk_direction[w][e],
+                )
+            })
+        },
+    )?;
+
+    read_array(
+        &v["player_death_count"],
+        &p("player_death_count"),
+        WORLD_COUNT,
+        |v, p, w| {
+            read_array(v, p, STAGE_COUNT, |v, p, s| {
+                read_u8(v, p, &mut slot.player_death_count[w][s])
+            })
+        },
+    )?;
+
+    read_u8(
+        &v["player_death_count_w3_l4_switch"],
+        &p("player_death_count_w3_l4_switch"),
+        &mut slot.player_death_count_w3_l4_switch,
+    )?;
+
+    Ok(slot)
+}
+
+impl SaveFile {
+    pub fn to_json(&self) -> JsonValue {
+        object! {
+            format: JSON_FORMAT_NAME,
+            format_version: JSON_FORMAT_VERSION,
+            header: header_to_json(&self.header),
+            slots: self.save_slots.iter().map(slot_to_json).collect::<Vec<_>>(),
+        }
+    }
+
+    /// Pretty-printed json, with lists of plain values kept on one line so that diffs stay readable.
+    pub fn to_json_string(&self) -> String {
+        let mut out = String::new();
+        write_pretty(&self.to_json(), 0, &mut out);
+        out.push('\n');
+
+        out
+    }
+
+    /// Builds a save file from a json document.
+    /// Bytes that the document doesn't describe are left as they are in a blank save.
+    pub fn from_json(input: &str) -> Result<Self, JsonError> {
+        let root = json::parse(input).map_err(JsonError::Parse)?;
+
+        if root["format"].as_str() != Some(JSON_FORMAT_NAME) {
+            return Err(invalid(
+                "format",
+                &format!("expected {:?}", JSON_FORMAT_NAME),
+            ));
+        }
+
+        let version = root["format_version"]
+            .as_u32()
+            .ok_or_else(|| invalid("format_version", "expected a non-negative integer"))?;
+
+        if version > JSON_FORMAT_VERSION {
+            return Err(JsonError::UnsupportedVersion(version));
+        }
+
+        let mut file = SaveFile::blank();
+
+        if !root["header"].is_null() {
+            file.header = read_header(&root["header"], "header")?;
+        }
+
+        read_array(&root["slots"], "slots", SAVE_SLOT_COUNT, |v, p, i| {
+            file.save_slots[i] = read_slot(v, p)?;
+            Ok(())
+        })?;
+
+        Ok(file)
+    }
+}
@@ -0,0 +1,173 @@
+use crate::savefile::constants::*;
+use crate::savefile::fields::{all_indices, format_path, FieldInfo, HEADER_FIELDS, SLOT_FIELDS};
+use crate::savefile::savediff::field_label;
+use crate::savefile::SaveFile;
+
+/// How to settle a field that was changed differently on both sides.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum MergeStrategy {
+    Ours,
+    Theirs,
+    Base,
+    Highest,
+}
+
+impl MergeStrategy {
+    pub const ALL: [MergeStrategy; 4] = [
+        MergeStrategy::Ours,
+        MergeStrategy::Theirs,
+        MergeStrategy::Base,
+        MergeStrategy::Highest,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            MergeStrategy::Ours => "ours",
+            MergeStrategy::Theirs => "theirs",
+            MergeStrategy::Base => "base",
+            MergeStrategy::Highest => "highest",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|s| s.name() == name)
+    }
+}
+
+/// A field that both sides changed to different values.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Conflict {
+    pub path: String,
+    pub label: String,
+    pub base: String,
+    pub ours: String,
+    pub theirs: String,
+    pub resolved: String,
+}
+
+pub struct MergeResult {
+    pub merged: SaveFile,
+    pub conflicts: Vec<Conflict>,
+}
+
+// progress that the game only ever adds to. when both sides changed it,
+// the result is the union of the two.
+const UNION_FIELDS: [&str; 5] = [
+    "stage_completion_flags",
+    "hint_movie_bought",
+    "world_unlocked",
+    "game_completion_flags",
+    "extra_modes_unlocked_worlds",
+];
+
+// counters that only go up. when both sides changed it, both increments are kept.
+const COUNTER_FIELDS: [&str; 2] = ["free_mode_play_count", "coin_battle_play_count"];
+
+fn merge_value(name: &str, max: u32, base: u32, ours: u32, theirs: u32) -> Option<u32> {
+    if ours == theirs || theirs == base {
+        return Some(ours);
+    }
+
+    if ours == base {
+        return Some(theirs);
+    }
+
+    if UNION_FIELDS.contains(&name) {
+        let mut merged = ours | theirs;
+
+        // the empty flag goes away with progress, so it only stays if both sides kept it
+        if name == "game_completion_flags" {
+            let empty = GameCompletionFlags::SaveEmpty.bits() as u32;
+            merged = (merged & !empty) | (ours & theirs & empty);
+        }
+
+        return Some(merged);
+    }
+
+    if COUNTER_FIELDS.contains(&name) && ours >= base && theirs >= base {
+        return Some((ours + theirs - base).min(max));
+    }
+
+    None
+}
+
+fn merge_fields<T>(
+    fields: &[FieldInfo<T>],
+    prefix: &str,
+    (base, ours, theirs): (&T, &T, &T),
+    out: &mut T,
+    strategy: MergeStrategy,
+    conflicts: &mut Vec<Conflict>,
+) {
+    for field in fields {
+        for index in all_indices(field.dims) {
+            let b = field.get(base, &index);
+            let o = field.get(ours, &index);
+            let t = field.get(theirs, &index);
+
+            let value = match merge_value(field.name, field.kind.max(), b, o, t) {
+                Some(v) => v,
+                None => {
+                    let resolved = match strategy {
+                        MergeStrategy::Ours => o,
+                        MergeStrategy::Theirs => t,
+                        MergeStrategy::Base => b,
+                        MergeStrategy::Highest => o.max(t),
+                    };
+
+                    conflicts.push(Conflict {
+                        path: format_path(prefix, field.name, &index),
+                        label: field_label(field.name, &index),
+                        base: field.kind.format(b),
+                        ours: field.kind.format(o),
+                        theirs: field.kind.format(t),
+                        resolved: field.kind.format(resolved),
+                    });
+
+                    resolved
+                }
+            };
+
+            field.set(out, &index, value);
+        }
+    }
+}
+
+/// Combines the changes that `ours` and `theirs` made to `base`, field by field.
+///
+/// Unmapped bytes are taken from `ours`.
+pub fn merge(
+    base: &SaveFile,
+    ours: &SaveFile,
+    theirs: &SaveFile,
+    strategy: MergeStrategy,
+) -> MergeResult {
+    let mut merged = ours.clone();
+    let mut conflicts = Vec::new();
+
+    merge_fields(
+        HEADER_FIELDS,
+        "header",
+        (&base.header, &ours.header, &theirs.header),
+        &mut merged.header,
+        strategy,
+        &mut conflicts,
+    );
+
+    for i in 0..SAVE_SLOT_COUNT {
+        merge_fields(
+            SLOT_FIELDS,
+            &format!("slot[{}]", i),
+            (
+                &base.save_slots[i],
+                &ours.save_slots[i],
+                &theirs.save_slots[i],
+            ),
+            &mut merged.save_slots[i],
+            strategy,
+            &mut conflicts,
+        );
+    }
+
+    MergeResult { merged, conflicts }
+}
@@ -1,10 +1,10 @@
 use byteorder::{BigEndian, ByteOrder};
 
+use crate::savefile::checksum::{slot_crc, SLOT_CRC_OFFSET};
 use crate::savefile::constants::*;
-use crc32fast as crc32;
-
-const SAVE_SLOT_SIZE: usize = 0x980;
+use crate::savefile::saveerror::SaveError;
 
+#[derive(Clone, PartialEq)]
 pub struct SaveSlot {
     pub game_completion_flags: u8,
     pub cur_world: u8,
@@ -32,6 +32,18 @@ pub struct SaveSlot {
     pub enemy_walk_direction: [[EnemyDirection; AMBUSH_ENEMY_COUNT]; WORLD_COUNT],
     pub player_death_count: [[u8; STAGE_COUNT]; WORLD_COUNT],
     pub player_death_count_w3_l4_switch: u8,
+
+    // the slot as it was read. edits are written over it so that
+    // anything the fields above don't cover survives a save
+    raw: Vec<u8>,
+}
+
+// only touches the byte if the value actually changed, so that
+// non-zero values other than 1 are kept
+fn write_bool(out: &mut u8, value: bool) {
+    if (*out != 0) != value {
+        *out = value as u8;
+    }
 }
 
 impl SaveSlot {
@@ -68,12 +80,92 @@ impl SaveSlot {
             enemy_walk_direction: [[EnemyDirection::ToNextNode; AMBUSH_ENEMY_COUNT]; WORLD_COUNT],
             player_death_count: [[0; STAGE_COUNT]; WORLD_COUNT],
             player_death_count_w3_l4_switch: 0,
+            raw: {
+                let mut raw = vec![0u8; SAVE_SLOT_SIZE];
+                raw[0] = SAVE_VERSION;
+                raw
+            },
         }
     }
 
-    pub fn from_bytes(input: &[u8], index: usize) -> Self {
+    /// An unused slot, as the game writes it when a slot is empty or has been reset.
+    pub fn empty() -> Self {
+        let mut slot = Self::blank();
+        slot.game_completion_flags = GameCompletionFlags::SaveEmpty.bits();
+
+        slot
+    }
+
+    /// A slot as the game writes it when a new game is started on it:
+    /// World 1 unlocked and the player on its first node, with the starting lives
+    /// and no ambush enemy placed yet.
+    pub fn new_game() -> Self {
+        let mut slot = Self::empty();
+        slot.game_completion_flags = 0;
+        slot.world_unlocked[0] = true;
+        slot.player_lives = [STARTING_LIVES; PLAYER_COUNT];
+        slot.enemy_walk_direction =
+            [[EnemyDirection::FirstTimeValue; AMBUSH_ENEMY_COUNT]; WORLD_COUNT];
+
+        slot
+    }
+
+    // 3-4 has a second counter that is used while the World 3 switch is on
+    fn uses_switch_counter(&self, world: usize, stage: usize) -> bool {
+        (world, stage) == (2, 3) && self.w3_switch_on
+    }
+
+    /// The death counter the game reads for a stage.
+    pub fn death_count(&self, world: usize, stage: usize) -> u8 {
+        if self.uses_switch_counter(world, stage) {
+            self.player_death_count_w3_l4_switch
+        } else {
+            self.player_death_count[world][stage]
+        }
+    }
+
+    pub fn death_count_mut(&mut self, world: usize, stage: usize) -> &mut u8 {
+        if self.uses_switch_counter(world, stage) {
+            &mut self.player_death_count_w3_l4_switch
+        } else {
+            &mut self.player_death_count[world][stage]
+        }
+    }
+
+    /// Whether the Super Guide block would appear in a stage.
+    pub fn offers_super_guide(&self, world: usize, stage: usize) -> bool {
+        self.death_count(world, stage) >= SUPER_GUIDE_DEATH_COUNT
+    }
+
+    /// Makes the Super Guide block appear the next time a stage is played.
+    pub fn force_super_guide(&mut self, world: usize, stage: usize) {
+        let count = self.death_count_mut(world, stage);
+        *count = (*count).max(SUPER_GUIDE_DEATH_COUNT);
+    }
+
+    pub fn clear_death_counts(&mut self) {
+        self.player_death_count = [[0; STAGE_COUNT]; WORLD_COUNT];
+        self.player_death_count_w3_l4_switch = 0;
+    }
+
+    pub fn from_bytes(input: &[u8], index: usize) -> Result<Self, SaveError> {
         let start_offset = HEADER_SIZE + (SAVE_SLOT_SIZE * index);
 
+        if input.len() < start_offset + SAVE_SLOT_SIZE {
+            return Err(SaveError::TooShort {
+                expected: start_offset + SAVE_SLOT_SIZE,
+                actual: input.len(),
+            });
+        }
+
+        if input[start_offset] != SAVE_VERSION {
+            return Err(SaveError::UnsupportedVersion {
+                version: input[start_offset],
+                offset: start_offset,
+                slot: Some(index),
+            });
+        }
+
         let game_completion_flags = input[start_offset + 2];
         let cur_world = input[start_offset + 3];
         let cur_subworld = input[start_offset + 4];
@@ -88,16 +180,8 @@ impl SaveSlot {
 
         let mut starting_mushroom_house_type: [StartingMushroomKind; WORLD_COUNT] =
             [StartingMushroomKind::None; WORLD_COUNT];
-        for i in 0..WORLD_COUNT {
-            starting_mushroom_house_type[i] = match input[start_offset + 0x10 + i] {
-                1 => StartingMushroomKind::Star,
-                2 => StartingMushroomKind::Item,
-                3 => StartingMushroomKind::OneUp,
-                4 => StartingMushroomKind::StarRescue,
-                5 => StartingMushroomKind::ItemRescue,
-                6 => StartingMushroomKind::OneUpRescue,
-                _ => StartingMushroomKind::None,
-            };
+        for (i, house_type) in starting_mushroom_house_type.iter_mut().enumerate() {
+            *house_type = input[start_offset + 0x10 + i].into();
         }
 
         let mut player_character = [
@@ -117,27 +201,8 @@ impl SaveSlot {
             player_coins[i] = input[start_offset + 0x1E + i];
             player_lives[i] = input[start_offset + 0x22 + i];
             player_spawn_flags[i] = input[start_offset + 0x26 + i];
-            player_character[i] = match input[start_offset + 0x2A + i] {
-                0 => PlayerCharacter::Mario,
-                1 => PlayerCharacter::Luigi,
-                2 => PlayerCharacter::BlueToad,
-                3 => PlayerCharacter::YellowToad,
-
-                // default to mario
-                _ => PlayerCharacter::Mario,
-            };
-            player_powerup[i] = match input[start_offset + 0x2E + i] {
-                0 => PlayerPowerup::None,
-                1 => PlayerPowerup::Mushroom,
-                2 => PlayerPowerup::FireFlower,
-                3 => PlayerPowerup::MiniMushroom,
-                4 => PlayerPowerup::PropellerMushroom,
-                5 => PlayerPowerup::PenguinSuit,
-                6 => PlayerPowerup::IceFlower,
-
-                // default to none
-                _ => PlayerPowerup::None,
-            };
+            player_character[i] = input[start_offset + 0x2A + i].into();
+            player_powerup[i] = input[start_offset + 0x2E + i].into();
         }
 
         let mut world_unlocked = [false; WORLD_COUNT];
@@ -160,14 +225,7 @@ impl SaveSlot {
                 enemy_revival_count[i][j] = input[start_offset + 0x3C + offs];
                 enemy_subworld[i][j] = input[start_offset + 0x74C + offs];
                 enemy_pos_index[i][j] = input[start_offset + 0x774 + offs];
-                enemy_walk_direction[i][j] = match input[start_offset + 0x79C + offs] {
-                    0 => EnemyDirection::ToNextNode,
-                    1 => EnemyDirection::ToPreviousNode,
-                    2 => EnemyDirection::FirstTimeValue,
-
-                    // default to next node
-                    _ => EnemyDirection::ToNextNode,
-                };
+                enemy_walk_direction[i][j] = input[start_offset + 0x79C + offs].into();
             }
 
             for j in 0..STAGE_COUNT {
@@ -191,7 +249,7 @@ impl SaveSlot {
 
         let player_death_count_w3_l4_switch = input[start_offset + 0x968];
 
-        Self {
+        Ok(Self {
             game_completion_flags,
             cur_world,
             cur_subworld,
@@ -218,21 +276,19 @@ impl SaveSlot {
             enemy_walk_direction,
             player_death_count,
             player_death_count_w3_l4_switch,
-        }
+            raw: input[start_offset..start_offset + SAVE_SLOT_SIZE].to_vec(),
+        })
     }
 
     pub fn to_bytes(&self) -> Vec<u8> {
-        let mut out = [0u8; SAVE_SLOT_SIZE];
-
-        // version
-        out[0] = 0xE;
+        let mut out = self.raw.clone();
 
         out[2] = self.game_completion_flags;
         out[3] = self.cur_world;
         out[4] = self.cur_subworld;
         out[5] = self.cur_path_node;
         out[6] = self.w5_vine_reshuffle_counter;
-        out[7] = if self.w3_switch_on { 1 } else { 0 };
+        write_bool(&mut out[7], self.w3_switch_on);
 
         out[9..(POWERUP_COUNT + 9)].copy_from_slice(&self.item_stock[..POWERUP_COUNT]);
 
@@ -242,36 +298,14 @@ impl SaveSlot {
             out[0x22 + i] = self.player_lives[i];
             out[0x26 + i] = self.player_spawn_flags[i];
 
-            out[0x2A + i] = match self.player_character[i] {
-                PlayerCharacter::Mario => 0,
-                PlayerCharacter::Luigi => 1,
-                PlayerCharacter::BlueToad => 2,
-                PlayerCharacter::YellowToad => 3,
-            };
-
-            out[0x2E + i] = match self.player_powerup[i] {
-                PlayerPowerup::None => 0,
-                PlayerPowerup::Mushroom => 1,
-                PlayerPowerup::FireFlower => 2,
-                PlayerPowerup::MiniMushroom => 3,
-                PlayerPowerup::PropellerMushroom => 4,
-                PlayerPowerup::PenguinSuit => 5,
-                PlayerPowerup::IceFlower => 6,
-            };
+            out[0x2A + i] = self.player_character[i].into();
+            out[0x2E + i] = self.player_powerup[i].into();
         }
 
         for i in 0..WORLD_COUNT {
-            out[0x10 + i] = match self.starting_mushroom_house_type[i] {
-                StartingMushroomKind::None => 0,
-                StartingMushroomKind::Star => 1,
-                StartingMushroomKind::Item => 2,
-                StartingMushroomKind::OneUp => 3,
-                StartingMushroomKind::StarRescue => 4,
-                StartingMushroomKind::ItemRescue => 5,
-                StartingMushroomKind::OneUpRescue => 6,
-            };
-
-            out[0x32 + i] = if self.world_unlocked[i] { 1 } else { 0 };
+            out[0x10 + i] = self.starting_mushroom_house_type[i].into();
+
+            write_bool(&mut out[0x32 + i], self.world_unlocked[i]);
             out[0x742 + i] = self.toad_rescue_level[i];
 
             for j in 0..AMBUSH_ENEMY_COUNT {
@@ -280,11 +314,7 @@ impl SaveSlot {
                 out[0x3C + offs] = self.enemy_revival_count[i][j];
                 out[0x74C + offs] = self.enemy_subworld[i][j];
                 out[0x774 + offs] = self.enemy_pos_index[i][j];
-                out[0x79C + offs] = match self.enemy_walk_direction[i][j] {
-                    EnemyDirection::ToNextNode => 0,
-                    EnemyDirection::ToPreviousNode => 1,
-                    EnemyDirection::FirstTimeValue => 2,
-                };
+                out[0x79C + offs] = self.enemy_walk_direction[i][j].into();
             }
 
             for j in 0..STAGE_COUNT {
@@ -303,15 +333,15 @@ impl SaveSlot {
         BigEndian::write_u32(&mut out[0x68..0x6C], self.ingame_score);
 
         for i in 0..HINT_MOVIE_COUNT {
-            out[0x6FC + i] = if self.hint_movie_bought[i] { 1 } else { 0 };
+            write_bool(&mut out[0x6FC + i], self.hint_movie_bought[i]);
         }
 
         out[0x968] = self.player_death_count_w3_l4_switch;
 
-        let crc = crc32::hash(&out[..SAVE_SLOT_SIZE - 4]);
+        let crc = slot_crc(&out);
 
-        BigEndian::write_u32(&mut out[0x97C..], crc);
+        BigEndian::write_u32(&mut out[SLOT_CRC_OFFSET..], crc);
 
-        out.to_vec()
+        out
     }
 }
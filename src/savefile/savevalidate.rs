@@ -0,0 +1,362 @@
+use json::{object, JsonValue};
+
+use crate::savefile::checksum::{CrcRegion, CrcReport};
+use crate::savefile::constants::*;
+use crate::savefile::fields::format_path;
+use crate::savefile::savediff::field_label;
+use crate::savefile::saveslot::SaveSlot;
+use crate::savefile::SaveFile;
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Severity {
+    /// Unusual, but the game handles it.
+    Warning,
+    /// A value the game never writes and may not cope with.
+    Error,
+}
+
+impl Severity {
+    pub fn name(self) -> &'static str {
+        match self {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+/// Something wrong with a single field.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Problem {
+    pub severity: Severity,
+    /// The slot the field is in, or `None` for the header.
+    pub slot: Option<usize>,
+    pub field: &'static str,
+    pub index: Vec<usize>,
+    pub message: String,
+}
+
+impl Problem {
+    pub(crate) fn new(
+        severity: Severity,
+        slot: Option<usize>,
+        field: &'static str,
+        index: &[usize],
+        message: String,
+    ) -> Self {
+        Self {
+            severity,
+            slot,
+            field,
+            index: index.to_vec(),
+            message,
+        }
+    }
+
+    /// The field path, such as `slot[0].item_stock[2]`.
+    pub fn path(&self) -> String {
+        let prefix = match self.slot {
+            Some(i) => format!("slot[{}]", i),
+            None => String::from("header"),
+        };
+
+        format_path(&prefix, self.field, &self.index)
+    }
+}
+
+// bits of `value` that aren't in the flag type
+fn unknown_bits<F: bitflags::Flags>(value: F::Bits) -> F::Bits {
+    F::from_bits_retain(value).difference(F::all()).bits()
+}
+
+fn check_header(file: &SaveFile, out: &mut Vec<Problem>) {
+    let header = &file.header;
+    let last = header.last_selected_index as usize;
+
+    if last >= NORMAL_SLOT_COUNT {
+        out.push(Problem::new(
+            Severity::Error,
+            None,
+            "last_selected_index",
+            &[],
+            format!("points at slot {}, which is not a save slot", last),
+        ));
+    } else if file.save_slots[last].is_empty()
+        && file.save_slots[..NORMAL_SLOT_COUNT]
+            .iter()
+            .any(|s| !s.is_empty())
+    {
+        out.push(Problem::new(
+            Severity::Warning,
+            None,
+            "last_selected_index",
+            &[],
+            format!("points at slot {}, which is empty", last),
+        ));
+    }
+
+    let extra = header.extra_modes_unlocked_worlds >> ACTUAL_WORLD_COUNT;
+
+    if extra != 0 {
+        out.push(Problem::new(
+            Severity::Warning,
+            None,
+            "extra_modes_unlocked_worlds",
+            &[],
+            format!(
+                "has bits set past World {} ({:#X})",
+                ACTUAL_WORLD_COUNT,
+                extra << ACTUAL_WORLD_COUNT
+            ),
+        ));
+    }
+}
+
+fn check_slot(slot: &SaveSlot, i: usize, out: &mut Vec<Problem>) {
+    let s = Some(i);
+
+    let unknown = unknown_bits::<GameCompletionFlags>(slot.game_completion_flags);
+
+    if unknown != 0 {
+        out.push(Problem::new(
+            Severity::Warning,
+            s,
+            "game_completion_flags",
+            &[],
+            format!("has unknown bits set ({:#X})", unknown),
+        ));
+    }
+
+    if slot.cur_world as usize >= ACTUAL_WORLD_COUNT {
+        out.push(Problem::new(
+            Severity::Error,
+            s,
+            "cur_world",
+            &[],
+            format!(
+                "is World {}, but there are only {} worlds",
+                slot.cur_world + 1,
+                ACTUAL_WORLD_COUNT
+            ),
+        ));
+    }
+
+    if slot.ingame_score > MAX_SCORE {
+        out.push(Problem::new(
+            Severity::Error,
+            s,
+            "ingame_score",
+            &[],
+            format!(
+                "{} is above the maximum of {}",
+                slot.ingame_score, MAX_SCORE
+            ),
+        ));
+    } else if !slot.ingame_score.is_multiple_of(50) {
+        out.push(Problem::new(
+            Severity::Warning,
+            s,
+            "ingame_score",
+            &[],
+            format!("{} is not a multiple of 50", slot.ingame_score),
+        ));
+    }
+
+    for (j, count) in slot.item_stock.iter().enumerate() {
+        if *count > POWERUP_STOCK_MAX {
+            out.push(Problem::new(
+                Severity::Error,
+                s,
+                "item_stock",
+                &[j],
+                format!("{} is above the maximum of {}", count, POWERUP_STOCK_MAX),
+            ));
+        }
+    }
+
+    for (p, lives) in slot.player_lives.iter().enumerate() {
+        if *lives > PLAYER_LIFE_MAX {
+            out.push(Problem::new(
+                Severity::Error,
+                s,
+                "player_lives",
+                &[p],
+                format!("{} is above the maximum of {}", lives, PLAYER_LIFE_MAX),
+            ));
+        }
+    }
+
+    for (p, character) in slot.player_character.iter().enumerate() {
+        if let PlayerCharacter::Unknown(v) = character {
+            out.push(Problem::new(
+                Severity::Error,
+                s,
+                "player_character",
+                &[p],
+                format!("{} is not a character", v),
+            ));
+        } else if let Some(first) = slot.player_character[..p]
+            .iter()
+            .position(|c| c == character)
+        {
+            out.push(Problem::new(
+                Severity::Error,
+                s,
+                "player_character",
+                &[p],
+                format!("{:?} is already player {}", character, first + 1),
+            ));
+        }
+    }
+
+    for (p, powerup) in slot.player_powerup.iter().enumerate() {
+        if let PlayerPowerup::Unknown(v) = powerup {
+            out.push(Problem::new(
+                Severity::Error,
+                s,
+                "player_powerup",
+                &[p],
+                format!("{} is not a powerup", v),
+            ));
+        }
+    }
+
+    for (p, flags) in slot.player_spawn_flags.iter().enumerate() {
+        let unknown = unknown_bits::<PlayerCreationFlags>(*flags);
+
+        if unknown != 0 {
+            out.push(Problem::new(
+                Severity::Warning,
+                s,
+                "player_spawn_flags",
+                &[p],
+                format!("has unknown bits set ({:#X})", unknown),
+            ));
+        }
+    }
+
+    for (w, kind) in slot.starting_mushroom_house_type.iter().enumerate() {
+        if let StartingMushroomKind::Unknown(v) = kind {
+            out.push(Problem::new(
+                Severity::Error,
+                s,
+                "starting_mushroom_house_type",
+                &[w],
+                format!("{} is not a mushroom house type", v),
+            ));
+        }
+    }
+
+    for (w, stages) in slot.stage_completion_flags.iter().enumerate() {
+        for (st, flags) in stages.iter().enumerate() {
+            let unknown = unknown_bits::<StageCompletionFlags>(*flags);
+
+            if unknown != 0 {
+                out.push(Problem::new(
+                    Severity::Warning,
+                    s,
+                    "stage_completion_flags",
+                    &[w, st],
+                    format!("has unknown bits set ({:#X})", unknown),
+                ));
+            }
+        }
+    }
+
+    for (w, directions) in slot.enemy_walk_direction.iter().enumerate() {
+        for (e, direction) in directions.iter().enumerate() {
+            if let EnemyDirection::Unknown(v) = direction {
+                out.push(Problem::new(
+                    Severity::Warning,
+                    s,
+                    "enemy_walk_direction",
+                    &[w, e],
+                    format!("{} is not a walk direction", v),
+                ));
+            }
+        }
+    }
+}
+
+impl SaveFile {
+    /// Checks every field for values the game would not write. Empty slots are skipped,
+    /// since the game ignores what is in them.
+    pub fn validate(&self) -> Vec<Problem> {
+        let mut out = Vec::new();
+
+        check_header(self, &mut out);
+
+        for (i, slot) in self.save_slots.iter().enumerate() {
+            if !slot.is_empty() {
+                check_slot(slot, i, &mut out);
+            }
+        }
+
+        out
+    }
+}
+
+/// Checksum mismatches as problems, so they can be reported alongside [`SaveFile::validate`].
+pub fn crc_problems(report: &CrcReport) -> Vec<Problem> {
+    report
+        .checks()
+        .filter(|c| !c.is_valid())
+        .map(|c| {
+            let slot = match c.region {
+                CrcRegion::Header => None,
+                CrcRegion::Slot(i) => Some(i),
+            };
+
+            Problem::new(
+                Severity::Error,
+                slot,
+                "checksum",
+                &[],
+                format!("is {:08X}, should be {:08X}", c.stored, c.expected),
+            )
+        })
+        .collect()
+}
+
+/// One problem per line, e.g. `error: slot[0].cur_world: is World 12, but there are only 9 worlds`.
+pub fn render_text(problems: &[Problem]) -> String {
+    problems
+        .iter()
+        .map(|p| {
+            let label = field_label(p.field, &p.index);
+
+            if label.is_empty() {
+                format!("{}: {}: {}", p.severity.name(), p.path(), p.message)
+            } else {
+                format!(
+                    "{}: {}: {} ({})",
+                    p.severity.name(),
+                    p.path(),
+                    p.message,
+                    label
+                )
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub fn render_json(problems: &[Problem]) -> JsonValue {
+    let count = |severity| problems.iter().filter(|p| p.severity == severity).count();
+
+    object! {
+        valid: count(Severity::Error) == 0,
+        errors: count(Severity::Error),
+        warnings: count(Severity::Warning),
+        problems: problems
+            .iter()
+            .map(|p| {
+                object! {
+                    severity: p.severity.name(),
+                    path: p.path(),
+                    label: field_label(p.field, &p.index),
+                    message: p.message.clone(),
+                }
+            })
+            .collect::<Vec<_>>(),
+    }
+}
@@ -0,0 +1,143 @@
+use std::fmt;
+
+use crate::savefile::constants::*;
+use crate::savefile::saveslot::SaveSlot;
+use crate::savefile::SaveFile;
+
+#[derive(Debug, PartialEq)]
+pub enum SlotError {
+    InvalidIndex(usize),
+    NotQuickSlot(usize),
+    EmptySlot(usize),
+}
+
+impl fmt::Display for SlotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SlotError::InvalidIndex(i) => {
+                write!(f, "{} is not a slot index (0-{})", i, SAVE_SLOT_COUNT - 1)
+            }
+            SlotError::NotQuickSlot(i) => write!(f, "slot {} is not a quick save slot", i),
+            SlotError::EmptySlot(i) => write!(f, "slot {} is empty", i),
+        }
+    }
+}
+
+impl std::error::Error for SlotError {}
+
+fn check(index: usize) -> Result<(), SlotError> {
+    if index >= SAVE_SLOT_COUNT {
+        return Err(SlotError::InvalidIndex(index));
+    }
+
+    Ok(())
+}
+
+impl SaveSlot {
+    pub fn is_empty(&self) -> bool {
+        self.game_completion_flags & GameCompletionFlags::SaveEmpty.bits() != 0
+    }
+
+    pub fn set_empty(&mut self, empty: bool) {
+        if empty {
+            self.game_completion_flags |= GameCompletionFlags::SaveEmpty.bits();
+        } else {
+            self.game_completion_flags &= !GameCompletionFlags::SaveEmpty.bits();
+        }
+    }
+}
+
+impl SaveFile {
+    /// Overwrites slot `to` with a copy of slot `from`.
+    pub fn copy_slot(&mut self, from: usize, to: usize) -> Result<(), SlotError> {
+        check(from)?;
+        check(to)?;
+
+        self.save_slots[to] = self.save_slots[from].clone();
+        self.fix_last_selected();
+
+        Ok(())
+    }
+
+    pub fn swap_slots(&mut self, a: usize, b: usize) -> Result<(), SlotError> {
+        check(a)?;
+        check(b)?;
+
+        self.save_slots.swap(a, b);
+
+        // the last selected slot follows its data
+        let last = self.header.last_selected_index as usize;
+
+        if last == a && b < NORMAL_SLOT_COUNT {
+            self.header.last_selected_index = b as u8;
+        } else if last == b && a < NORMAL_SLOT_COUNT {
+            self.header.last_selected_index = a as u8;
+        }
+
+        self.fix_last_selected();
+
+        Ok(())
+    }
+
+    /// Resets a slot to the state the game writes for an unused slot.
+    pub fn clear_slot(&mut self, index: usize) -> Result<(), SlotError> {
+        check(index)?;
+
+        self.save_slots[index] = SaveSlot::empty();
+        self.fix_last_selected();
+
+        Ok(())
+    }
+
+    /// Starts a new game on a slot, as if it had been picked on the file select screen.
+    pub fn start_new_game(&mut self, index: usize) -> Result<(), SlotError> {
+        check(index)?;
+
+        self.save_slots[index] = SaveSlot::new_game();
+
+        if index < NORMAL_SLOT_COUNT {
+            self.header.last_selected_index = index as u8;
+        }
+
+        Ok(())
+    }
+
+    /// Turns a quick save into a normal save in the slot it belongs to,
+    /// and empties the quick save slot, like loading a quick save in the game does.
+    pub fn promote_quick_save(&mut self, index: usize) -> Result<(), SlotError> {
+        check(index)?;
+
+        if index < NORMAL_SLOT_COUNT {
+            return Err(SlotError::NotQuickSlot(index));
+        }
+
+        if self.save_slots[index].is_empty() {
+            return Err(SlotError::EmptySlot(index));
+        }
+
+        let target = index - NORMAL_SLOT_COUNT;
+
+        self.save_slots[target] = self.save_slots[index].clone();
+        self.save_slots[target].set_empty(false);
+        self.save_slots[index] = SaveSlot::empty();
+
+        self.header.last_selected_index = target as u8;
+
+        Ok(())
+    }
+
+    // the last selected index should point at a normal slot that has data in it
+    fn fix_last_selected(&mut self) {
+        let last = self.header.last_selected_index as usize;
+
+        if last < NORMAL_SLOT_COUNT && !self.save_slots[last].is_empty() {
+            return;
+        }
+
+        if let Some(i) = (0..NORMAL_SLOT_COUNT).find(|i| !self.save_slots[*i].is_empty()) {
+            self.header.last_selected_index = i as u8;
+        } else if last >= NORMAL_SLOT_COUNT {
+            self.header.last_selected_index = 0;
+        }
+    }
+}
@@ -0,0 +1,359 @@
+//! What the game has in each world: which stage indices are real stages, what they
+//! are called in game, and which have secret exits and star coins.
+//!
+//! The course lists add up to the game's star coin count: 69 courses (207 coins) in
+//! Worlds 1-8 and 77 (231 coins) with World 9. The secret exits are only the ones the
+//! hint movies point out, so a course may have one the tables don't know about; don't
+//! throw away progress based on them.
+
+use crate::savefile::constants::*;
+use crate::savefile::get_stage_name_string;
+
+/// Stage indices shared by every world.
+pub const GHOST_HOUSE: usize = 20;
+pub const TOWER_1: usize = 21;
+pub const CASTLE_1: usize = 23;
+pub const TOWER_2: usize = 22;
+pub const CASTLE_2: usize = 24;
+pub const CANNON: usize = 35;
+pub const AIRSHIP: usize = 37;
+
+/// The world whose stages count towards `AllStarCoinsSpe` instead of `AllStarCoinsReg`.
+pub const SPECIAL_WORLD: usize = 8;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum StageKind {
+    Numbered,
+    GhostHouse,
+    Tower,
+    Castle,
+    Airship,
+    ToadHouse,
+    EnemyAmbush,
+    Cannon,
+    /// Title screen, Peach's Castle, the credits and the like: not on any world map.
+    Special,
+    Unused,
+}
+
+impl StageKind {
+    /// What kind of stage an index is, whichever world it is in.
+    pub fn of(stage: usize) -> Self {
+        match stage {
+            0..=8 => StageKind::Numbered,
+            GHOST_HOUSE => StageKind::GhostHouse,
+            TOWER_1 | TOWER_2 => StageKind::Tower,
+            CASTLE_1 | CASTLE_2 => StageKind::Castle,
+            25..=28 => StageKind::ToadHouse,
+            32..=34 => StageKind::EnemyAmbush,
+            CANNON => StageKind::Cannon,
+            AIRSHIP => StageKind::Airship,
+            19 | 38..=41 => StageKind::Special,
+            _ => StageKind::Unused,
+        }
+    }
+
+    /// Whether stages of this kind have star coins and a goal.
+    pub fn is_course(self) -> bool {
+        matches!(
+            self,
+            StageKind::Numbered
+                | StageKind::GhostHouse
+                | StageKind::Tower
+                | StageKind::Castle
+                | StageKind::Airship
+        )
+    }
+}
+
+/// A playable stage: one with star coins and a goal.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Course {
+    pub stage: usize,
+    pub kind: StageKind,
+    pub secret_exit: bool,
+}
+
+const fn numbered(stage: usize, secret_exit: bool) -> Course {
+    Course {
+        stage,
+        kind: StageKind::Numbered,
+        secret_exit,
+    }
+}
+
+const fn course(stage: usize, kind: StageKind, secret_exit: bool) -> Course {
+    Course {
+        stage,
+        kind,
+        secret_exit,
+    }
+}
+
+use StageKind::*;
+
+// secret exits are the ones the hint movies point out
+const WORLD_1: &[Course] = &[
+    numbered(0, false),
+    numbered(1, false),
+    numbered(2, true),
+    numbered(3, false),
+    numbered(4, false),
+    numbered(5, false),
+    course(TOWER_1, Tower, false),
+    course(CASTLE_1, Castle, false),
+];
+
+const WORLD_2: &[Course] = &[
+    numbered(0, false),
+    numbered(1, false),
+    numbered(2, false),
+    numbered(3, true),
+    numbered(4, false),
+    numbered(5, true),
+    course(TOWER_1, Tower, false),
+    course(CASTLE_1, Castle, false),
+];
+
+const WORLD_3: &[Course] = &[
+    numbered(0, false),
+    numbered(1, false),
+    numbered(2, false),
+    numbered(3, false),
+    numbered(4, false),
+    course(GHOST_HOUSE, GhostHouse, true),
+    course(TOWER_1, Tower, false),
+    course(CASTLE_1, Castle, false),
+];
+
+const WORLD_4: &[Course] = &[
+    numbered(0, false),
+    numbered(1, false),
+    numbered(2, false),
+    numbered(3, false),
+    numbered(4, false),
+    course(GHOST_HOUSE, GhostHouse, true),
+    course(TOWER_1, Tower, true),
+    course(CASTLE_1, Castle, false),
+    course(AIRSHIP, Airship, false),
+];
+
+const WORLD_5: &[Course] = &[
+    numbered(0, false),
+    numbered(1, false),
+    numbered(2, false),
+    numbered(3, false),
+    numbered(4, false),
+    course(GHOST_HOUSE, GhostHouse, true),
+    course(TOWER_1, Tower, false),
+    course(CASTLE_1, Castle, false),
+];
+
+const WORLD_6: &[Course] = &[
+    numbered(0, false),
+    numbered(1, false),
+    numbered(2, false),
+    numbered(3, false),
+    numbered(4, true),
+    numbered(5, true),
+    course(TOWER_1, Tower, false),
+    course(CASTLE_1, Castle, false),
+    course(AIRSHIP, Airship, false),
+];
+
+const WORLD_7: &[Course] = &[
+    numbered(0, false),
+    numbered(1, false),
+    numbered(2, false),
+    numbered(3, false),
+    numbered(4, false),
+    numbered(5, false),
+    course(GHOST_HOUSE, GhostHouse, true),
+    course(TOWER_1, Tower, true),
+    course(CASTLE_1, Castle, false),
+];
+
+const WORLD_8: &[Course] = &[
+    numbered(0, false),
+    numbered(1, true),
+    numbered(2, false),
+    numbered(3, false),
+    numbered(4, false),
+    numbered(5, false),
+    numbered(6, false),
+    course(TOWER_1, Tower, false),
+    course(AIRSHIP, Airship, false),
+    course(CASTLE_2, Castle, false),
+];
+
+const WORLD_9: &[Course] = &[
+    numbered(0, false),
+    numbered(1, false),
+    numbered(2, false),
+    numbered(3, false),
+    numbered(4, false),
+    numbered(5, false),
+    numbered(6, false),
+    numbered(7, false),
+];
+
+/// The playable stages of a world, or nothing for the unused tenth world.
+pub fn world_courses(world: usize) -> &'static [Course] {
+    match world {
+        0 => WORLD_1,
+        1 => WORLD_2,
+        2 => WORLD_3,
+        3 => WORLD_4,
+        4 => WORLD_5,
+        5 => WORLD_6,
+        6 => WORLD_7,
+        7 => WORLD_8,
+        8 => WORLD_9,
+        _ => &[],
+    }
+}
+
+pub fn find_course(world: usize, stage: usize) -> Option<Course> {
+    world_courses(world)
+        .iter()
+        .copied()
+        .find(|c| c.stage == stage)
+}
+
+/// Whether `stage` is a course in any world.
+pub fn is_course_index(stage: usize) -> bool {
+    (0..ACTUAL_WORLD_COUNT).any(|w| find_course(w, stage).is_some())
+}
+
+/// The castle that has to be cleared to leave a world, if it has one.
+pub fn world_castle(world: usize) -> Option<usize> {
+    world_courses(world)
+        .iter()
+        .find(|c| c.kind == Castle)
+        .map(|c| c.stage)
+}
+
+// worlds with a warp cannon; the cannon is reached through a secret exit
+const CANNON_WORLDS: usize = 6;
+
+/// One entry of the stage catalogue.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct StageInfo {
+    pub world: usize,
+    pub stage: usize,
+    pub kind: StageKind,
+    /// Whether the world has this stage. Special stages aren't in any world.
+    pub exists: bool,
+    pub secret_exit: bool,
+    pub star_coins: bool,
+}
+
+impl StageInfo {
+    /// The name the game uses, such as `1-3`, `3-Ghost House` or `8-Airship`.
+    pub fn name(&self) -> String {
+        let w = self.world + 1;
+
+        match self.kind {
+            Numbered => format!("{}-{}", w, self.stage + 1),
+            GhostHouse => format!("{}-Ghost House", w),
+            // only World 8's castle uses the second index, and no world has two towers
+            Tower | Castle if self.exists => {
+                format!(
+                    "{}-{}",
+                    w,
+                    if self.kind == Tower {
+                        "Tower"
+                    } else {
+                        "Castle"
+                    }
+                )
+            }
+            Tower | Castle | ToadHouse | EnemyAmbush => {
+                format!("{}-{}", w, get_stage_name_string(self.stage))
+            }
+            Airship => format!("{}-Airship", w),
+            Cannon => format!("{}-Cannon", w),
+            Special | Unused => get_stage_name_string(self.stage),
+        }
+    }
+
+    /// [`StageInfo::name`], marked when the world doesn't have the stage.
+    pub fn label(&self) -> String {
+        if self.exists || self.kind == Special {
+            self.name()
+        } else {
+            format!("{} (not in this world)", self.name())
+        }
+    }
+}
+
+/// Looks up a stage index in a world.
+pub fn stage_info(world: usize, stage: usize) -> StageInfo {
+    let kind = StageKind::of(stage);
+    let course = find_course(world, stage);
+    let story_world = world < SPECIAL_WORLD;
+
+    let exists = match kind {
+        ToadHouse | EnemyAmbush => story_world,
+        Cannon => world < CANNON_WORLDS,
+        Special | Unused => false,
+        _ => course.is_some(),
+    };
+
+    StageInfo {
+        world,
+        stage,
+        kind,
+        exists,
+        secret_exit: course.is_some_and(|c| c.secret_exit),
+        star_coins: course.is_some(),
+    }
+}
+
+/// Shorthand for `stage_info(world, stage).name()`.
+pub fn stage_name(world: usize, stage: usize) -> String {
+    stage_info(world, stage).name()
+}
+
+/// Every stage a world has, in index order.
+pub fn world_stages(world: usize) -> impl Iterator<Item = StageInfo> {
+    (0..STAGE_COUNT)
+        .map(move |stage| stage_info(world, stage))
+        .filter(|info| info.exists)
+}
+
+const STAR_COINS: u32 = StageCompletionFlags::StarCoin1.bits()
+    | StageCompletionFlags::StarCoin2.bits()
+    | StageCompletionFlags::StarCoin3.bits();
+
+/// Whether every course in `worlds` has all three star coins collected.
+pub fn all_star_coins(flags: &[[u32; STAGE_COUNT]; WORLD_COUNT], worlds: &[usize]) -> bool {
+    worlds.iter().all(|&w| {
+        world_courses(w)
+            .iter()
+            .all(|c| flags[w][c.stage] & STAR_COINS == STAR_COINS)
+    })
+}
+
+/// Whether every goal, secret ones included, has been reached in every world.
+pub fn all_goals(flags: &[[u32; STAGE_COUNT]; WORLD_COUNT]) -> bool {
+    (0..ACTUAL_WORLD_COUNT).all(|w| {
+        world_courses(w).iter().all(|c| {
+            let mut needed = StageCompletionFlags::GoalNormal.bits();
+
+            if c.secret_exit {
+                needed |= StageCompletionFlags::GoalSecret.bits();
+            }
+
+            flags[w][c.stage] & needed == needed
+        })
+    })
+}
+
+/// The world a hint movie belongs to, taken from its title.
+pub fn hint_movie_world(movie: usize) -> Option<usize> {
+    let title = HINT_MOVIE_TITLES.get(movie)?;
+    let digit = title.chars().next()?.to_digit(10)?;
+
+    Some(digit as usize - 1)
+}
@@ -1,8 +1,12 @@
 use std::fs;
+use std::path::{Path, PathBuf};
 
+use crate::backup::{DEFAULT_BACKUP_COUNT, DEFAULT_BACKUP_DIR_NAME};
 use anyhow::Result;
 use eframe::egui;
-use json::{self, object};
+use json::{self, object, JsonValue};
+
+pub const SETTINGS_PATH: &str = "penguin_settings.json";
 
 #[derive(Clone, Copy, PartialEq)]
 enum PenguinTheme {
@@ -12,6 +16,10 @@ enum PenguinTheme {
 
 pub struct PenguinSettings {
     theme: PenguinTheme,
+    backup_directory: Option<PathBuf>,
+    backup_count: usize,
+    sync_completion_flags: bool,
+    presets_file: Option<PathBuf>,
 }
 
 fn theme_to_string(theme: PenguinTheme) -> String {
@@ -26,10 +34,35 @@ impl PenguinSettings {
     pub fn default() -> Self {
         Self {
             theme: PenguinTheme::Dark,
+            backup_directory: None,
+            backup_count: DEFAULT_BACKUP_COUNT,
+            sync_completion_flags: false,
+            presets_file: None,
         }
     }
 
-    pub fn show_ui(&mut self, ui: &mut egui::Ui) {
+    /// Where backups go. `None` means a folder next to each save file.
+    pub fn backup_directory(&self) -> Option<&Path> {
+        self.backup_directory.as_deref()
+    }
+
+    /// How many backups to keep per file. 0 turns backups off.
+    pub fn backup_count(&self) -> usize {
+        self.backup_count
+    }
+
+    /// Whether the completion flags that follow from the stage flags are recomputed after every edit.
+    pub fn sync_completion_flags(&self) -> bool {
+        self.sync_completion_flags
+    }
+
+    /// A json file with presets to offer alongside the built-in ones.
+    pub fn presets_file(&self) -> Option<&Path> {
+        self.presets_file.as_deref()
+    }
+
+    /// Returns whether the user asked for the settings to be saved.
+    pub fn show_ui(&mut self, ui: &mut egui::Ui) -> bool {
         egui::ComboBox::from_label("Theme")
             .selected_text(theme_to_string(self.theme))
             .show_ui(ui, |ui| {
@@ -43,9 +76,91 @@ impl PenguinSettings {
                 }
             });
 
-        if ui.button("Save settings").clicked() {
-            self.save();
-        }
+        ui.separator();
+        ui.label("Backups");
+
+        ui.horizontal(|ui| {
+            ui.label("Directory:");
+
+            match &self.backup_directory {
+                Some(dir) => ui.monospace(dir.display().to_string()),
+                None => ui.label(format!(
+                    "\"{}\" next to the save file",
+                    DEFAULT_BACKUP_DIR_NAME
+                )),
+            };
+        });
+
+        ui.horizontal(|ui| {
+            if ui.button("Choose...").clicked() {
+                if let Some(dir) = rfd::FileDialog::new()
+                    .set_can_create_directories(true)
+                    .pick_folder()
+                {
+                    self.backup_directory = Some(dir);
+                }
+            }
+
+            if ui
+                .add_enabled(
+                    self.backup_directory.is_some(),
+                    egui::Button::new("Use default"),
+                )
+                .clicked()
+            {
+                self.backup_directory = None;
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Backups to keep:");
+            ui.add(egui::DragValue::new(&mut self.backup_count).range(0..=100));
+        })
+        .response
+        .on_hover_text("A copy of the file is made before each save. Set to 0 to turn this off.");
+
+        ui.separator();
+        ui.label("Editing");
+
+        ui.checkbox(
+            &mut self.sync_completion_flags,
+            "Keep completion flags in sync with stages",
+        )
+        .on_hover_text(
+            "Recompute \"All goals\", both \"All star coins\" flags and \"Game completed\" \
+             whenever the file is edited.",
+        );
+
+        ui.horizontal(|ui| {
+            ui.label("Presets file:");
+
+            match &self.presets_file {
+                Some(file) => ui.monospace(file.display().to_string()),
+                None => ui.label("None, built-in presets only"),
+            };
+        });
+
+        ui.horizontal(|ui| {
+            if ui.button("Choose...").clicked() {
+                if let Some(file) = rfd::FileDialog::new()
+                    .add_filter("Presets", &["json"])
+                    .pick_file()
+                {
+                    self.presets_file = Some(file);
+                }
+            }
+
+            if ui
+                .add_enabled(self.presets_file.is_some(), egui::Button::new("Remove"))
+                .clicked()
+            {
+                self.presets_file = None;
+            }
+        });
+
+        ui.separator();
+
+        ui.button("Save settings").clicked()
     }
 
     pub fn update_theme(&self, ctx: &egui::Context) {
@@ -56,31 +171,83 @@ impl PenguinSettings {
     }
 
     pub fn load(&mut self) -> Result<()> {
-        let exists = fs::exists("penguin_settings.json")?;
+        let exists = fs::exists(SETTINGS_PATH)?;
 
         if !exists {
-            self.save();
-
-            return Ok(());
+            return self.save();
         }
 
-        let contents = fs::read_to_string("penguin_settings.json")?;
-        let parsed = json::parse(&contents)?;
+        let contents = fs::read_to_string(SETTINGS_PATH)?;
+        self.read_json(&json::parse(&contents)?);
 
-        self.theme = match parsed["theme"].as_str().unwrap() {
+        Ok(())
+    }
+
+    fn read_json(&mut self, parsed: &JsonValue) {
+        self.theme = match parsed["theme"].as_str().unwrap_or_default() {
             "light" => PenguinTheme::Light,
             "dark" => PenguinTheme::Dark,
             _ => PenguinTheme::Dark,
         };
 
+        self.backup_directory = parsed["backup_directory"].as_str().map(PathBuf::from);
+        self.backup_count = parsed["backup_count"]
+            .as_usize()
+            .unwrap_or(DEFAULT_BACKUP_COUNT);
+        self.sync_completion_flags = parsed["sync_completion_flags"].as_bool().unwrap_or(false);
+        self.presets_file = parsed["presets_file"].as_str().map(PathBuf::from);
+    }
+
+    pub fn save(&self) -> Result<()> {
+        fs::write(SETTINGS_PATH, self.to_json().to_string())?;
+
         Ok(())
     }
 
-    pub fn save(&self) {
-        let contents = object!(
-            theme: theme_to_string(self.theme).to_lowercase()
-        );
+    fn to_json(&self) -> JsonValue {
+        object!(
+            theme: theme_to_string(self.theme).to_lowercase(),
+            backup_directory: self.backup_directory.as_ref().map(|d| d.display().to_string()),
+            backup_count: self.backup_count,
+            sync_completion_flags: self.sync_completion_flags,
+            presets_file: self.presets_file.as_ref().map(|f| f.display().to_string()),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_survive_a_round_trip() {
+        let mut settings = PenguinSettings::default();
+        settings.theme = PenguinTheme::Light;
+        settings.backup_directory = Some(PathBuf::from("backups"));
+        settings.backup_count = 3;
+        settings.sync_completion_flags = true;
+        settings.presets_file = Some(PathBuf::from("presets.json"));
+
+        let mut loaded = PenguinSettings::default();
+        loaded.read_json(&json::parse(&settings.to_json().to_string()).unwrap());
+
+        assert!(loaded.theme == PenguinTheme::Light);
+        assert_eq!(loaded.backup_directory(), Some(Path::new("backups")));
+        assert_eq!(loaded.backup_count(), 3);
+        assert!(loaded.sync_completion_flags());
+        assert_eq!(loaded.presets_file(), Some(Path::new("presets.json")));
+    }
 
-        let _ = fs::write("penguin_settings.json", contents.to_string());
+    #[test]
+    fn missing_settings_fall_back_to_the_defaults() {
+        let mut loaded = PenguinSettings::default();
+        loaded.presets_file = Some(PathBuf::from("presets.json"));
+        loaded.read_json(&json::parse("{}").unwrap());
+
+        assert!(loaded.theme == PenguinTheme::Dark);
+        assert_eq!(loaded.backup_directory(), None);
+        assert_eq!(loaded.backup_count(), DEFAULT_BACKUP_COUNT);
+        assert!(!loaded.sync_completion_flags());
+        assert_eq!(loaded.presets_file(), None);
     }
 }
@@ -1,5 +1,6 @@
 pub mod header_view;
 pub mod slot_view;
+pub mod stage_grid;
 
 #[derive(Clone, Copy, PartialEq)]
 pub enum PenguinView {
@@ -1,33 +1,187 @@
 use crate::savefile::{
-    constants::{SaveFileRegion, ACTUAL_WORLD_COUNT},
+    constants::{SaveFileRegion, ACTUAL_WORLD_COUNT, STAGE_COUNT, WORLD_COUNT},
     saveheader::SaveHeader,
+    stages::{stage_name, world_courses, StageKind},
 };
 use eframe::egui;
 
-pub struct HeaderView;
+#[derive(Clone, Copy, PartialEq)]
+enum PlayCountMode {
+    FreeMode,
+    CoinBattle,
+}
+
+pub struct HeaderView {
+    play_count_mode: PlayCountMode,
+    sort_by_plays: bool,
+    set_all_value: u16,
+}
+
+// one column per kind of course, so that each world's castle lines up even though
+// World 8 uses a different index for it
+const PLAY_COUNT_COLUMNS: [(StageKind, Option<usize>); 12] = [
+    (StageKind::Numbered, Some(0)),
+    (StageKind::Numbered, Some(1)),
+    (StageKind::Numbered, Some(2)),
+    (StageKind::Numbered, Some(3)),
+    (StageKind::Numbered, Some(4)),
+    (StageKind::Numbered, Some(5)),
+    (StageKind::Numbered, Some(6)),
+    (StageKind::Numbered, Some(7)),
+    (StageKind::GhostHouse, None),
+    (StageKind::Tower, None),
+    (StageKind::Castle, None),
+    (StageKind::Airship, None),
+];
+
+fn column_title(column: (StageKind, Option<usize>)) -> String {
+    match column {
+        (_, Some(stage)) => format!("{}", stage + 1),
+        (StageKind::GhostHouse, _) => String::from("Ghost House"),
+        (StageKind::Tower, _) => String::from("Tower"),
+        (StageKind::Castle, _) => String::from("Castle"),
+        (StageKind::Airship, _) => String::from("Airship"),
+        _ => String::new(),
+    }
+}
+
+// the stage index a world uses for a column, if it has that stage
+fn column_stage(world: usize, column: (StageKind, Option<usize>)) -> Option<usize> {
+    world_courses(world)
+        .iter()
+        .find(|c| c.kind == column.0 && column.1.is_none_or(|s| s == c.stage))
+        .map(|c| c.stage)
+}
 
 impl HeaderView {
     pub fn new() -> Self {
-        Self
+        Self {
+            play_count_mode: PlayCountMode::FreeMode,
+            sort_by_plays: false,
+            set_all_value: 0,
+        }
+    }
+
+    fn show_play_counts(&mut self, ui: &mut egui::Ui, header: &mut SaveHeader) {
+        ui.horizontal(|ui| {
+            ui.label("Play counts");
+            ui.selectable_value(
+                &mut self.play_count_mode,
+                PlayCountMode::FreeMode,
+                "Free Mode",
+            );
+            ui.selectable_value(
+                &mut self.play_count_mode,
+                PlayCountMode::CoinBattle,
+                "Coin Battle",
+            );
+            ui.separator();
+            ui.checkbox(&mut self.sort_by_plays, "Sort by most played");
+        });
+
+        ui.horizontal(|ui| {
+            let counts = match self.play_count_mode {
+                PlayCountMode::FreeMode => &mut header.free_mode_play_count,
+                PlayCountMode::CoinBattle => &mut header.coin_battle_play_count,
+            };
+
+            if ui.button("Zero all").clicked() {
+                *counts = [[0; STAGE_COUNT]; WORLD_COUNT];
+            }
+
+            if ui
+                .button("Set all to")
+                .on_hover_text("Sets every stage that exists to this count.")
+                .clicked()
+            {
+                for (w, row) in counts.iter_mut().enumerate() {
+                    for course in world_courses(w) {
+                        row[course.stage] = self.set_all_value;
+                    }
+                }
+            }
+
+            ui.add(egui::DragValue::new(&mut self.set_all_value));
+
+            ui.separator();
+
+            if ui
+                .button("Copy Free Mode to Coin Battle")
+                .on_hover_text("Overwrites every Coin Battle count with the Free Mode one.")
+                .clicked()
+            {
+                header.coin_battle_play_count = header.free_mode_play_count;
+            }
+        });
+
+        let counts = match self.play_count_mode {
+            PlayCountMode::FreeMode => &mut header.free_mode_play_count,
+            PlayCountMode::CoinBattle => &mut header.coin_battle_play_count,
+        };
+
+        if self.sort_by_plays {
+            let mut played: Vec<(usize, usize)> = (0..ACTUAL_WORLD_COUNT)
+                .flat_map(|w| world_courses(w).iter().map(move |c| (w, c.stage)))
+                .filter(|&(w, s)| counts[w][s] > 0)
+                .collect();
+
+            // stable, so ties stay in world order
+            played.sort_by_key(|&(w, s)| std::cmp::Reverse(counts[w][s]));
+
+            if played.is_empty() {
+                ui.label("Nothing has been played yet.");
+                return;
+            }
+
+            egui::Grid::new("play_counts_sorted")
+                .striped(true)
+                .show(ui, |ui| {
+                    for (w, s) in played {
+                        ui.label(stage_name(w, s));
+                        ui.add(egui::DragValue::new(&mut counts[w][s]));
+                        ui.end_row();
+                    }
+                });
+
+            return;
+        }
+
+        egui::Grid::new("play_counts").striped(true).show(ui, |ui| {
+            ui.label("");
+
+            for column in PLAY_COUNT_COLUMNS {
+                ui.label(column_title(column));
+            }
+
+            ui.end_row();
+
+            for (w, row) in counts.iter_mut().enumerate().take(ACTUAL_WORLD_COUNT) {
+                ui.label(format!("World {}", w + 1));
+
+                for column in PLAY_COUNT_COLUMNS {
+                    match column_stage(w, column) {
+                        Some(s) => {
+                            ui.add(egui::DragValue::new(&mut row[s]))
+                                .on_hover_text(stage_name(w, s));
+                        }
+                        None => {
+                            ui.label("");
+                        }
+                    }
+                }
+
+                ui.end_row();
+            }
+        });
     }
 
     pub fn show_ui(&mut self, ui: &mut egui::Ui, header: &mut SaveHeader) {
         egui::ComboBox::from_label("Region")
-            .selected_text(match header.region {
-                SaveFileRegion::NTSC => "North America",
-                SaveFileRegion::PAL => "Europe/Australia",
-                SaveFileRegion::JPN => "Japan",
-                SaveFileRegion::KOR => "Korea",
-                SaveFileRegion::CHN => "China",
-                SaveFileRegion::TW => "Taiwan",
-            })
+            .selected_text(header.region.name())
             .show_ui(ui, |ui| {
-                ui.selectable_value(&mut header.region, SaveFileRegion::NTSC, "North America");
-                ui.selectable_value(&mut header.region, SaveFileRegion::PAL, "Europe/Australia");
-                ui.selectable_value(&mut header.region, SaveFileRegion::JPN, "Japan");
-                ui.selectable_value(&mut header.region, SaveFileRegion::KOR, "Korea");
-                ui.selectable_value(&mut header.region, SaveFileRegion::CHN, "China");
-                ui.selectable_value(&mut header.region, SaveFileRegion::TW, "Taiwan");
+                for region in SaveFileRegion::ALL {
+                    ui.selectable_value(&mut header.region, region, region.name());
+                }
             });
 
         egui::ComboBox::from_label("Last played save slot")
@@ -39,7 +193,9 @@ impl HeaderView {
                 }
             });
 
-        // the play counts in the extra modes are omitted for now
+        ui.add_space(3.0);
+
+        self.show_play_counts(ui, header);
 
         ui.add_space(3.0);
 
@@ -1,11 +1,18 @@
 use eframe::egui;
 
-use crate::savefile::{constants::*, saveslot::SaveSlot};
+use crate::savefile::{
+    constants::*,
+    saveconsistency::DERIVED_COMPLETION_FLAGS,
+    saveslot::SaveSlot,
+    stages::{stage_name, world_stages},
+};
+use crate::views::stage_grid::StageGrid;
 
 pub struct SlotView {
     world_edit_index: usize,
-    stage_edit_index: usize,
+    stage_grid: StageGrid,
     player_edit_index: usize,
+    sync_completion_flags: bool,
 }
 
 fn get_house_type_string(house_type: StartingMushroomKind) -> String {
@@ -17,35 +24,251 @@ fn get_house_type_string(house_type: StartingMushroomKind) -> String {
         StartingMushroomKind::StarRescue => "Star (Rescue)",
         StartingMushroomKind::ItemRescue => "Item (Rescue)",
         StartingMushroomKind::OneUpRescue => "1-Up (Rescue)",
+        StartingMushroomKind::Unknown(v) => return format!("Unknown ({})", v),
     }
     .to_string()
 }
 
-fn get_stage_name_string(stage_index: usize) -> String {
-    match stage_index {
-        0..=8 => format!("Stage {}", stage_index + 1),
-        19 => String::from("Coin Battle Stage"),
-        20 => String::from("Ghost House"),
-        21 | 22 => format!("Tower {}", stage_index - 20),
-        23 | 24 => format!("Castle {}", stage_index - 22),
-        25..=28 => format!("Toad House {}", stage_index - 24),
-        32..=34 => format!("Enemy Ambush {}", stage_index - 31),
-        35 => String::from("Cannon"),
-        37 => String::from("Airship"),
-        38 => String::from("\"Rescue\""),
-        39 => String::from("Title Screen"),
-        40 => String::from("Peach's Castle"),
-        41 => String::from("Staff Credits"),
-        _ => format!("(unused index {})", stage_index),
+const ENEMY_DIRECTIONS: [EnemyDirection; 3] = [
+    EnemyDirection::ToNextNode,
+    EnemyDirection::ToPreviousNode,
+    EnemyDirection::FirstTimeValue,
+];
+
+fn get_direction_string(direction: EnemyDirection) -> String {
+    match direction {
+        EnemyDirection::ToNextNode => "Forwards",
+        EnemyDirection::ToPreviousNode => "Backwards",
+        EnemyDirection::FirstTimeValue => "Not placed yet",
+        EnemyDirection::Unknown(v) => return format!("Unknown ({})", v),
     }
+    .to_string()
 }
 
+const SPAWN_FLAGS: [(PlayerCreationFlags, &str, &str); 4] = [
+    (
+        PlayerCreationFlags::StarPower,
+        "Star Power",
+        "Starts the next stage invincible, as if a Super Star was still active.",
+    ),
+    (
+        PlayerCreationFlags::Yoshi,
+        "Yoshi",
+        "Starts the next stage riding the Yoshi that was brought out of the last one.",
+    ),
+    (
+        PlayerCreationFlags::Bubble,
+        "Bubble",
+        "Starts the next stage floating in a bubble, like a player who lost a life in multiplayer.",
+    ),
+    (
+        PlayerCreationFlags::RescueToad,
+        "Rescue Toad",
+        "Starts the next stage carrying the Toad from a Toad rescue level.",
+    ),
+];
+
 impl SlotView {
     pub fn new() -> Self {
         Self {
             world_edit_index: 0,
-            stage_edit_index: 0,
+            stage_grid: StageGrid::new(),
             player_edit_index: 0,
+            sync_completion_flags: false,
+        }
+    }
+
+    /// With syncing on, the derived completion flags are kept up to date by the app
+    /// and can't be ticked by hand.
+    pub fn set_sync_completion_flags(&mut self, sync: bool) {
+        self.sync_completion_flags = sync;
+    }
+
+    /// Points the world, stage and player pickers at an indexed field, so that it is on screen.
+    pub fn focus(&mut self, field: &str, index: &[usize]) {
+        match index {
+            [w, s] if field == "stage_completion_flags" || field == "player_death_count" => {
+                self.world_edit_index = *w;
+                self.stage_grid.select(*s);
+            }
+
+            [w, ..] if field.starts_with("enemy_") => self.world_edit_index = *w,
+
+            [p] if field.starts_with("player_") => self.player_edit_index = *p,
+
+            [w] if field != "item_stock" && field != "hint_movie_bought" => {
+                self.world_edit_index = *w;
+            }
+
+            _ => {}
+        }
+    }
+
+    /// Every field of the ambush enemies in the selected world.
+    fn show_ambush_enemies(&mut self, ui: &mut egui::Ui, slot: &mut SaveSlot) {
+        let w = self.world_edit_index;
+
+        ui.label(format!("Ambush enemies in World {}", w + 1));
+
+        egui::Grid::new("ambush_enemies")
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("");
+                ui.label("Subworld");
+                ui.label("Node").on_hover_text(
+                    "Not checked, since how many nodes each subworld has is not known.",
+                );
+                ui.label("Walking")
+                    .on_hover_text("Which way along the path the enemy moves after each turn.");
+                ui.label("Revivals")
+                    .on_hover_text("How many times the enemy has come back after being beaten.");
+                ui.end_row();
+
+                for e in 0..AMBUSH_ENEMY_COUNT {
+                    ui.label(format!("Enemy {}", e + 1));
+
+                    // left as-is when out of range, so the warning below can point it out
+                    ui.add(
+                        egui::DragValue::new(&mut slot.enemy_subworld[w][e])
+                            .range(0..=MAX_SUBWORLD)
+                            .clamp_existing_to_range(false),
+                    );
+
+                    // the game's node numbers count every junction and toad house on the way
+                    ui.add(
+                        egui::DragValue::new(&mut slot.enemy_pos_index[w][e]).range(0..=u8::MAX),
+                    );
+
+                    egui::ComboBox::from_id_salt(("enemy_direction", e))
+                        .selected_text(get_direction_string(slot.enemy_walk_direction[w][e]))
+                        .show_ui(ui, |ui| {
+                            for direction in ENEMY_DIRECTIONS {
+                                ui.selectable_value(
+                                    &mut slot.enemy_walk_direction[w][e],
+                                    direction,
+                                    get_direction_string(direction),
+                                );
+                            }
+                        });
+
+                    ui.add(
+                        egui::DragValue::new(&mut slot.enemy_revival_count[w][e])
+                            .range(0..=u8::MAX),
+                    );
+
+                    // the subworld and node counts of each world's map aren't known, so this only
+                    // catches subworlds no world has. a bad node in a real subworld goes unreported
+                    if slot.enemy_subworld[w][e] > MAX_SUBWORLD {
+                        ui.colored_label(ui.visuals().warn_fg_color, "⚠")
+                            .on_hover_text(format!(
+                                "The game has no subworld {} in any world.",
+                                slot.enemy_subworld[w][e] + 1
+                            ));
+                    }
+
+                    ui.end_row();
+                }
+            });
+    }
+
+    /// Deaths per stage in the selected world. Eight deaths make the Super Guide block appear.
+    fn show_death_counts(&mut self, ui: &mut egui::Ui, slot: &mut SaveSlot) {
+        let w = self.world_edit_index;
+
+        ui.vertical(|ui| {
+            ui.label(format!("Deaths in World {}", w + 1))
+                .on_hover_text(format!(
+                    "After {} deaths in a stage, the Super Guide block appears at its start.",
+                    SUPER_GUIDE_DEATH_COUNT
+                ));
+
+            if ui
+                .button("Clear all")
+                .on_hover_text("Resets the death counts of every world.")
+                .clicked()
+            {
+                slot.clear_death_counts();
+            }
+
+            egui::Grid::new("death_counts")
+                .striped(true)
+                .show(ui, |ui| {
+                    for info in world_stages(w).filter(|info| info.star_coins) {
+                        let s = info.stage;
+
+                        if slot.offers_super_guide(w, s) {
+                            ui.colored_label(ui.visuals().warn_fg_color, info.name())
+                                .on_hover_text("The Super Guide block appears in this stage.");
+                        } else {
+                            ui.label(info.name());
+                        }
+
+                        ui.add(egui::DragValue::new(slot.death_count_mut(w, s)).range(0..=u8::MAX));
+
+                        if ui
+                            .add_enabled(!slot.offers_super_guide(w, s), egui::Button::new("Force"))
+                            .on_hover_text(
+                                "Raises the count so the Super Guide block appears here.",
+                            )
+                            .clicked()
+                        {
+                            slot.force_super_guide(w, s);
+                        }
+
+                        ui.end_row();
+                    }
+                });
+
+            // the counter that isn't in use right now, so both can be edited
+            if w == 2 {
+                let (label, count) = if slot.w3_switch_on {
+                    ("3-4 (switch off)", &mut slot.player_death_count[2][3])
+                } else {
+                    ("3-4 (switch on)", &mut slot.player_death_count_w3_l4_switch)
+                };
+
+                ui.horizontal(|ui| {
+                    ui.label(label).on_hover_text(
+                        "3-4 counts deaths separately depending on the World 3 switch.",
+                    );
+                    ui.add(egui::DragValue::new(count).range(0..=u8::MAX));
+                });
+            }
+        });
+    }
+
+    /// Every bit of the selected player's spawn flags, the undocumented ones included.
+    fn show_spawn_flags(&mut self, ui: &mut egui::Ui, slot: &mut SaveSlot) {
+        let flags = &mut slot.player_spawn_flags[self.player_edit_index];
+
+        ui.label(format!("Spawn flags ({:#04X})", *flags))
+            .on_hover_text("How the player appears when the next stage starts.");
+
+        // every bit gets a checkbox, so flags nobody has documented can still be set and cleared
+        for bit in (0..8).map(|i| 1 << i) {
+            let mut is_checked = *flags & bit != 0;
+
+            let documented = SPAWN_FLAGS.iter().find(|(flag, _, _)| flag.bits() == bit);
+
+            let (label, explanation) = match documented {
+                Some((_, label, explanation)) => (label.to_string(), *explanation),
+                None => (
+                    format!("Unknown ({:#04X})", bit),
+                    "Not a flag the game is known to use.",
+                ),
+            };
+
+            if ui
+                .checkbox(&mut is_checked, label)
+                .on_hover_text(explanation)
+                .changed()
+            {
+                if is_checked {
+                    *flags |= bit;
+                } else {
+                    *flags &= !bit;
+                }
+            }
         }
     }
 
@@ -71,8 +294,10 @@ impl SlotView {
         
                     for (i, label) in labels.iter().enumerate() {
                         let mut is_checked = (slot.game_completion_flags & (1 << i)) != 0;
+                        let derived = DERIVED_COMPLETION_FLAGS.bits() & (1 << i) != 0;
+                        let enabled = !(derived && self.sync_completion_flags);
         
-                        if ui.checkbox(&mut is_checked, *label).changed() {
+                        if ui.add_enabled(enabled, egui::Checkbox::new(&mut is_checked, *label)).changed() {
                             if is_checked {
                                 slot.game_completion_flags |= 1 << i;
                             } else {
@@ -80,6 +305,14 @@ impl SlotView {
                             }
                         }
                     }
+
+                    if ui
+                        .add_enabled(!self.sync_completion_flags, egui::Button::new("Recompute"))
+                        .on_hover_text("Set the goal and star coin flags from the stages")
+                        .clicked()
+                    {
+                        slot.sync_completion_flags();
+                    }
                 });
     
                 ui.vertical(|ui| {
@@ -110,6 +343,7 @@ impl SlotView {
                             egui::DragValue::new(&mut slot.ingame_score)
                             .speed(1)
                             .range(0..=MAX_SCORE)
+                            .clamp_existing_to_range(false)
                         );
                     });
                     ui.add_space(3.0);
@@ -130,7 +364,8 @@ impl SlotView {
                         ui.add(
                             egui::DragValue::new(&mut slot.item_stock[i])
                             .speed(1)
-                            .range(0..=POWERUP_STOCK_MAX)          
+                            .range(0..=POWERUP_STOCK_MAX)
+                            .clamp_existing_to_range(false)
                         );
                         ui.label(*item_name);
                     });
@@ -199,14 +434,14 @@ impl SlotView {
                 });
                 egui::ComboBox::from_label("Toad Rescue Level")
                 .selected_text(
-                    get_stage_name_string(slot.toad_rescue_level[self.world_edit_index] as usize)
+                    stage_name(self.world_edit_index, slot.toad_rescue_level[self.world_edit_index] as usize)
                 )
                 .show_ui(ui, |ui|{
                     for i in 0..=STAGE_COUNT {
                         ui.selectable_value(
                             &mut slot.toad_rescue_level[self.world_edit_index],
                             i as u8,
-                            get_stage_name_string(i)
+                            stage_name(self.world_edit_index, i)
                         ).on_hover_text(
                             "After rescuing a toad from a level, the game will set this stage index to one that is normally inaccessible."
                         );
@@ -214,49 +449,11 @@ impl SlotView {
                 });
                 ui.vertical(|ui|{
                     // stage completion
-                    egui::ComboBox::from_label("Selected Stage")
-                    .selected_text(
-                        get_stage_name_string(self.stage_edit_index)
-                    )
-                    .show_ui(ui, |ui|{
-                        for i in 0..=STAGE_COUNT {
-                            ui.selectable_value(
-                                &mut self.stage_edit_index,
-                                i,
-                                get_stage_name_string(i)
-                            );
-                        }
-                    });
-                    
-                    ui.label("Stage Completion Flags");
-                    let labels = [
-                        "Star Coin 1",
-                        "Star Coin 2",
-                        "Star Coin 3",
-                        "Goal (Normal)",
-                        "Goal (Secret)",
-                        "Super Guide (Normal)",
-                        "Super Guide (Secret)"
-                    ];
-                    let flags = [
-                        1, 2, 4, 0x10, 0x20, 0x80, 0x100
-                    ];
-                    for i in 0..labels.len() {
-                        let mut is_checked = (
-                            slot.stage_completion_flags[self.world_edit_index][self.stage_edit_index]
-                                & flags[i]
-                        ) != 0;
-                        
-                        if ui.checkbox(&mut is_checked, labels[i]).changed() {
-                            if is_checked {
-                                slot.stage_completion_flags[self.world_edit_index][self.stage_edit_index]
-                                    |= flags[i]
-                            } else {
-                                slot.stage_completion_flags[self.world_edit_index][self.stage_edit_index]
-                                    &= !flags[i];
-                            }
-                        }
-                    }
+                    self.stage_grid.show(
+                        ui,
+                        self.world_edit_index,
+                        &mut slot.stage_completion_flags[self.world_edit_index]
+                    );
                 });
             });
 
@@ -281,14 +478,18 @@ impl SlotView {
                 ui.add(
                     egui::DragValue::new(&mut slot.cur_subworld)
                     .speed(1)
-                    .range(0..=1)
+                    .range(0..=MAX_SUBWORLD)
+                    .clamp_existing_to_range(false)
                 );
-                ui.label("Current path node");
+                ui.label("Current path node")
+                .on_hover_text("The game counts every junction and toad house on the path, not just the stages.");
                 ui.add(
                     egui::DragValue::new(&mut slot.cur_path_node)
                     .speed(1)
                     .range(0..=u8::MAX)
                 );
+                ui.add_space(3.0);
+                self.show_ambush_enemies(ui, slot);
                 // *player* information
 
                 ui.vertical(|ui|{
@@ -306,10 +507,11 @@ impl SlotView {
                     egui::ComboBox::from_label("Character")
                     .selected_text(
                         match slot.player_character[self.player_edit_index] {
-                            PlayerCharacter::Mario => "Mario",
-                            PlayerCharacter::Luigi => "Luigi",
-                            PlayerCharacter::BlueToad => "Blue Toad",
-                            PlayerCharacter::YellowToad => "Yellow Toad"
+                            PlayerCharacter::Mario => String::from("Mario"),
+                            PlayerCharacter::Luigi => String::from("Luigi"),
+                            PlayerCharacter::BlueToad => String::from("Blue Toad"),
+                            PlayerCharacter::YellowToad => String::from("Yellow Toad"),
+                            PlayerCharacter::Unknown(v) => format!("Unknown ({})", v)
                         }
                     )
                     .show_ui(ui, |ui|{
@@ -351,6 +553,7 @@ impl SlotView {
                                 egui::DragValue::new(&mut slot.player_lives[self.player_edit_index])
                                 .speed(1)
                                 .range(0..=PLAYER_LIFE_MAX)
+                                .clamp_existing_to_range(false)
                             );
                         });
                     });
@@ -358,13 +561,8 @@ impl SlotView {
                     egui::ComboBox::from_label("Powerup")
                     .selected_text(
                         match slot.player_powerup[self.player_edit_index] {
-                            PlayerPowerup::None => "None",
-                            PlayerPowerup::Mushroom => "Mushroom",
-                            PlayerPowerup::FireFlower => "Fire Flower",
-                            PlayerPowerup::MiniMushroom => "Mini Mushroom",
-                            PlayerPowerup::PropellerMushroom => "Propeller Mushroom",
-                            PlayerPowerup::PenguinSuit => "Penguin Suit",
-                            PlayerPowerup::IceFlower => "Ice Flower",
+                            PlayerPowerup::Unknown(v) => format!("Unknown ({})", v),
+                            p => PLAYER_POWERUP_STATUS[u8::from(p) as usize].to_string(),
                         }
                     ).show_ui(ui, |ui|{
                         for (i, status) in PLAYER_POWERUP_STATUS.iter().enumerate() {
@@ -387,19 +585,13 @@ impl SlotView {
                         }
                     });
                     
-                    // omitting the other flags for now since they don't do anything
-                    {
-                        let mut is_checked = (slot.player_spawn_flags[self.player_edit_index] & PlayerCreationFlags::StarPower.bits()) != 0;
-                        if ui.checkbox(&mut is_checked, "Star Power").changed() {
-                            if is_checked {
-                                slot.player_spawn_flags[self.player_edit_index] |= PlayerCreationFlags::StarPower.bits();
-                            } else {
-                                slot.player_spawn_flags[self.player_edit_index] &= !PlayerCreationFlags::StarPower.bits();
-                            }
-                        }
-                    }
+                    self.show_spawn_flags(ui, slot);
                 });
             });
+
+            ui.separator();
+
+            self.show_death_counts(ui, slot);
         });
         });
     }
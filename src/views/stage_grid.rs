@@ -0,0 +1,251 @@
+use eframe::egui;
+use egui::{Key, Modifiers};
+
+use crate::savefile::{
+    constants::{StageCompletionFlags, STAGE_COUNT},
+    stages::{stage_info, world_stages, StageInfo},
+};
+
+const COLUMNS: [(StageCompletionFlags, &str, &str); 7] = [
+    (StageCompletionFlags::StarCoin1, "1", "Star Coin 1"),
+    (StageCompletionFlags::StarCoin2, "2", "Star Coin 2"),
+    (StageCompletionFlags::StarCoin3, "3", "Star Coin 3"),
+    (StageCompletionFlags::GoalNormal, "Goal", "Goal (Normal)"),
+    (StageCompletionFlags::GoalSecret, "Secret", "Goal (Secret)"),
+    (
+        StageCompletionFlags::SuperGuideGoalNormal,
+        "SG",
+        "Super Guide (Normal)",
+    ),
+    (
+        StageCompletionFlags::SuperGuideGoalSecret,
+        "SG Secret",
+        "Super Guide (Secret)",
+    ),
+];
+
+const KEYBOARD_HELP: &str = "Click a cell, then use the arrow keys to move, \
+     Space to toggle the cell, R to toggle the row and C to toggle the column.";
+
+// the flags a stage can actually earn. only courses of this world have any, so the
+// toggles leave toad houses, cannons and stages the world doesn't have alone
+fn applicable(info: &StageInfo) -> u32 {
+    if !info.star_coins {
+        return 0;
+    }
+
+    let mut flags = StageCompletionFlags::StarCoin1
+        | StageCompletionFlags::StarCoin2
+        | StageCompletionFlags::StarCoin3
+        | StageCompletionFlags::GoalNormal
+        | StageCompletionFlags::SuperGuideGoalNormal;
+
+    if info.secret_exit {
+        flags |= StageCompletionFlags::GoalSecret | StageCompletionFlags::SuperGuideGoalSecret;
+    }
+
+    flags.bits()
+}
+
+// sets `mask` on every stage, or clears it if it's already set everywhere
+fn toggle(flags: &mut [u32; STAGE_COUNT], cells: &[(usize, u32)]) {
+    let all_set = cells.iter().all(|&(s, mask)| flags[s] & mask == mask);
+
+    for &(s, mask) in cells {
+        if all_set {
+            flags[s] &= !mask;
+        } else {
+            flags[s] |= mask;
+        }
+    }
+}
+
+/// The completion flags of a whole world as a grid: stages are rows, flags are columns.
+pub struct StageGrid {
+    stage: usize,
+    column: usize,
+    has_keyboard: bool,
+}
+
+impl StageGrid {
+    pub fn new() -> Self {
+        Self {
+            stage: 0,
+            column: 0,
+            has_keyboard: false,
+        }
+    }
+
+    /// Moves the cursor to a stage's row.
+    pub fn select(&mut self, stage: usize) {
+        self.stage = stage;
+    }
+
+    fn handle_keys(&mut self, ui: &egui::Ui, rows: &[StageInfo], flags: &mut [u32; STAGE_COUNT]) {
+        if !self.has_keyboard || ui.ctx().wants_keyboard_input() {
+            return;
+        }
+
+        let Some(mut row) = rows.iter().position(|r| r.stage == self.stage) else {
+            return;
+        };
+
+        let pressed = |key| ui.input_mut(|i| i.consume_key(Modifiers::NONE, key));
+
+        if pressed(Key::ArrowUp) {
+            row = row.saturating_sub(1);
+        }
+
+        if pressed(Key::ArrowDown) {
+            row = (row + 1).min(rows.len() - 1);
+        }
+
+        if pressed(Key::ArrowLeft) {
+            self.column = self.column.saturating_sub(1);
+        }
+
+        if pressed(Key::ArrowRight) {
+            self.column = (self.column + 1).min(COLUMNS.len() - 1);
+        }
+
+        let info = &rows[row];
+        let bit = COLUMNS[self.column].0.bits();
+        self.stage = info.stage;
+
+        if pressed(Key::Space) && (applicable(info) | flags[info.stage]) & bit != 0 {
+            flags[info.stage] ^= bit;
+        }
+
+        if pressed(Key::R) {
+            toggle(flags, &[(info.stage, applicable(info))]);
+        }
+
+        if pressed(Key::C) {
+            let cells: Vec<(usize, u32)> = rows
+                .iter()
+                .filter(|r| applicable(r) & bit != 0)
+                .map(|r| (r.stage, bit))
+                .collect();
+
+            toggle(flags, &cells);
+        }
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui, world: usize, flags: &mut [u32; STAGE_COUNT]) {
+        // stages the world doesn't have are only listed if something is set in them
+        let mut rows: Vec<StageInfo> = world_stages(world).collect();
+
+        rows.extend(
+            (0..STAGE_COUNT)
+                .map(|s| stage_info(world, s))
+                .filter(|info| !info.exists && flags[info.stage] != 0),
+        );
+
+        if !rows.iter().any(|r| r.stage == self.stage) {
+            self.stage = rows.first().map(|r| r.stage).unwrap_or(0);
+        }
+
+        self.handle_keys(ui, &rows, flags);
+
+        ui.horizontal(|ui| {
+            ui.label("Stage completion flags")
+                .on_hover_text(KEYBOARD_HELP);
+
+            let everything: Vec<(usize, u32)> =
+                rows.iter().map(|r| (r.stage, applicable(r))).collect();
+
+            if ui
+                .button("Toggle world")
+                .on_hover_text("Sets every flag the world's stages can have, or clears them all.")
+                .clicked()
+            {
+                toggle(flags, &everything);
+            }
+        });
+
+        let mut clicked_cell = false;
+
+        let response = egui::Grid::new(("stage_grid", world))
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("");
+
+                for (column, (bit, short, full)) in COLUMNS.iter().enumerate() {
+                    if ui
+                        .small_button(*short)
+                        .on_hover_text(format!("{}: click to toggle the column", full))
+                        .clicked()
+                    {
+                        let cells: Vec<(usize, u32)> = rows
+                            .iter()
+                            .filter(|r| applicable(r) & bit.bits() != 0)
+                            .map(|r| (r.stage, bit.bits()))
+                            .collect();
+
+                        toggle(flags, &cells);
+                        self.column = column;
+                    }
+                }
+
+                ui.end_row();
+
+                for info in &rows {
+                    let s = info.stage;
+
+                    if ui
+                        .selectable_label(self.stage == s, info.label())
+                        .on_hover_text("Click to toggle the row")
+                        .clicked()
+                    {
+                        toggle(flags, &[(s, applicable(info))]);
+                        self.stage = s;
+                        clicked_cell = true;
+                    }
+
+                    for (column, (bit, _, full)) in COLUMNS.iter().enumerate() {
+                        let bit = bit.bits();
+                        let mut is_checked = flags[s] & bit != 0;
+
+                        // a flag the stage can't have stays clickable while set, so it can be cleared
+                        let enabled = (applicable(info) | flags[s]) & bit != 0;
+
+                        let cell = ui
+                            .add_enabled(enabled, egui::Checkbox::without_text(&mut is_checked))
+                            .on_hover_text(format!("{}: {}", info.name(), full));
+
+                        if cell.changed() {
+                            flags[s] ^= bit;
+                        }
+
+                        if cell.clicked() {
+                            self.stage = s;
+                            self.column = column;
+                            clicked_cell = true;
+                        }
+
+                        if self.has_keyboard && self.stage == s && self.column == column {
+                            ui.painter().rect_stroke(
+                                cell.rect.expand(2.0),
+                                2.0,
+                                ui.visuals().selection.stroke,
+                            );
+                        }
+                    }
+
+                    ui.end_row();
+                }
+            })
+            .response;
+
+        // the grid keeps the keyboard until something else is clicked
+        if clicked_cell {
+            self.has_keyboard = true;
+        } else if ui.input(|i| i.pointer.any_click())
+            && !response
+                .rect
+                .contains(ui.input(|i| i.pointer.interact_pos()).unwrap_or_default())
+        {
+            self.has_keyboard = false;
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use penguin::savefile::{
+    checksum::repair_checksums,
+    constants::{SaveFileRegion, HEADER_SIZE, SAVE_SLOT_SIZE},
+    saveslot::SaveSlot,
+    SaveFile,
+};
+
+fn valid_bytes() -> Vec<u8> {
+    let mut file = SaveFile::new(SaveFileRegion::NTSC);
+    file.start_new_game(0).unwrap();
+    file.start_new_game(2).unwrap();
+
+    let mut bytes = file.to_bytes();
+    repair_checksums(&mut bytes).unwrap();
+
+    bytes
+}
+
+#[test]
+fn valid_file_has_no_corrupt_slots() {
+    let (_, report) = SaveFile::from_bytes_checked(&valid_bytes()).unwrap();
+
+    assert!(report.is_valid());
+}
+
+#[test]
+fn unparseable_corrupt_slot_loads_as_empty() {
+    let mut bytes = valid_bytes();
+
+    // a slot whose version byte is garbage, and whose checksum no longer matches
+    bytes[HEADER_SIZE + SAVE_SLOT_SIZE * 2] = 0xFF;
+
+    assert!(SaveFile::from_bytes(&bytes).is_err());
+
+    let (mut file, report) = SaveFile::from_bytes_checked(&bytes).unwrap();
+
+    assert_eq!(report.corrupt_slots(), vec![2]);
+    assert!(file.save_slots[2] == SaveSlot::empty());
+    assert!(!file.save_slots[0].is_empty());
+
+    assert_eq!(file.reset_corrupt_slots(&report), vec![2]);
+}
+
+#[test]
+fn unparseable_slot_with_a_valid_checksum_is_an_error() {
+    let mut bytes = valid_bytes();
+    bytes[HEADER_SIZE + SAVE_SLOT_SIZE * 2] = 0xFF;
+    repair_checksums(&mut bytes).unwrap();
+
+    assert!(SaveFile::from_bytes_checked(&bytes).is_err());
+}
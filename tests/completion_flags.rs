@@ -0,0 +1,84 @@
+use penguin::savefile::{constants::*, saveslot::SaveSlot, stages::world_courses};
+
+const COINS: u32 = StageCompletionFlags::StarCoin1.bits()
+    | StageCompletionFlags::StarCoin2.bits()
+    | StageCompletionFlags::StarCoin3.bits();
+
+fn slot_with(goals: bool, coins_up_to: usize) -> SaveSlot {
+    let mut slot = SaveSlot::new_game();
+
+    for w in 0..ACTUAL_WORLD_COUNT {
+        for course in world_courses(w) {
+            let flags = &mut slot.stage_completion_flags[w][course.stage];
+
+            if goals {
+                *flags |= StageCompletionFlags::GoalNormal.bits();
+
+                if course.secret_exit {
+                    *flags |= StageCompletionFlags::GoalSecret.bits();
+                }
+            }
+
+            if w < coins_up_to {
+                *flags |= COINS;
+            }
+        }
+    }
+
+    slot
+}
+
+fn derived(slot: &SaveSlot) -> GameCompletionFlags {
+    slot.derived_completion_flags()
+}
+
+#[test]
+fn all_goals_alone_only_derives_all_goals() {
+    assert_eq!(derived(&slot_with(true, 0)), GameCompletionFlags::AllGoals);
+}
+
+#[test]
+fn a_missing_secret_exit_means_not_all_goals() {
+    let mut slot = slot_with(true, 0);
+    let secret = world_courses(0).iter().find(|c| c.secret_exit).unwrap();
+    slot.stage_completion_flags[0][secret.stage] &= !StageCompletionFlags::GoalSecret.bits();
+
+    assert_eq!(derived(&slot), GameCompletionFlags::empty());
+}
+
+#[test]
+fn star_coins_are_split_between_the_regular_worlds_and_world_9() {
+    assert_eq!(
+        derived(&slot_with(false, 8)),
+        GameCompletionFlags::AllStarCoinsReg
+    );
+    assert_eq!(
+        derived(&slot_with(false, ACTUAL_WORLD_COUNT)),
+        GameCompletionFlags::AllStarCoinsReg | GameCompletionFlags::AllStarCoinsSpe
+    );
+}
+
+// the assumed rule: everything earned and the final boss beaten
+#[test]
+fn game_completed_needs_everything_and_the_final_boss() {
+    let mut slot = slot_with(true, ACTUAL_WORLD_COUNT);
+    let everything = GameCompletionFlags::AllGoals
+        | GameCompletionFlags::AllStarCoinsReg
+        | GameCompletionFlags::AllStarCoinsSpe;
+
+    assert_eq!(derived(&slot), everything);
+
+    slot.game_completion_flags |= GameCompletionFlags::FinalBossBeaten.bits();
+
+    assert_eq!(
+        derived(&slot),
+        everything | GameCompletionFlags::GameCompleted
+    );
+
+    assert!(slot.sync_completion_flags());
+    assert_eq!(
+        slot.game_completion_flags,
+        (everything | GameCompletionFlags::GameCompleted | GameCompletionFlags::FinalBossBeaten)
+            .bits()
+    );
+}
@@ -0,0 +1,55 @@
+use penguin::savefile::{constants::*, saveslot::SaveSlot};
+
+#[test]
+fn world_3_4_uses_the_switch_counter_while_the_switch_is_on() {
+    let mut slot = SaveSlot::new_game();
+    slot.player_death_count[2][3] = 2;
+    slot.player_death_count_w3_l4_switch = 6;
+
+    assert_eq!(slot.death_count(2, 3), 2);
+
+    slot.w3_switch_on = true;
+    assert_eq!(slot.death_count(2, 3), 6);
+
+    *slot.death_count_mut(2, 3) = 7;
+    assert_eq!(slot.player_death_count_w3_l4_switch, 7);
+    assert_eq!(slot.player_death_count[2][3], 2);
+
+    // only 3-4 has a second counter
+    assert_eq!(slot.death_count(2, 2), 0);
+    assert_eq!(slot.death_count(3, 3), 0);
+}
+
+#[test]
+fn super_guide_appears_from_the_threshold_on() {
+    let mut slot = SaveSlot::new_game();
+
+    slot.player_death_count[0][0] = SUPER_GUIDE_DEATH_COUNT - 1;
+    assert!(!slot.offers_super_guide(0, 0));
+
+    slot.player_death_count[0][0] = SUPER_GUIDE_DEATH_COUNT;
+    assert!(slot.offers_super_guide(0, 0));
+}
+
+#[test]
+fn forcing_the_super_guide_never_lowers_a_count() {
+    let mut slot = SaveSlot::new_game();
+    slot.player_death_count[0][1] = 20;
+
+    slot.force_super_guide(0, 0);
+    slot.force_super_guide(0, 1);
+
+    assert_eq!(slot.player_death_count[0][0], SUPER_GUIDE_DEATH_COUNT);
+    assert_eq!(slot.player_death_count[0][1], 20);
+
+    slot.w3_switch_on = true;
+    slot.force_super_guide(2, 3);
+    assert_eq!(
+        slot.player_death_count_w3_l4_switch,
+        SUPER_GUIDE_DEATH_COUNT
+    );
+
+    slot.clear_death_counts();
+    assert_eq!(slot.player_death_count, [[0; STAGE_COUNT]; WORLD_COUNT]);
+    assert_eq!(slot.player_death_count_w3_l4_switch, 0);
+}
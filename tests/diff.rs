@@ -0,0 +1,55 @@
+use penguin::savefile::{
+    constants::*,
+    savediff::{diff, Change},
+    SaveFile,
+};
+
+fn started() -> SaveFile {
+    let mut file = SaveFile::new(SaveFileRegion::NTSC);
+    file.start_new_game(1).unwrap();
+
+    file
+}
+
+#[test]
+fn identical_files_have_no_changes() {
+    assert_eq!(diff(&started(), &started()), vec![]);
+}
+
+#[test]
+fn one_value_change_is_reported_once() {
+    let old = started();
+    let mut new = old.clone();
+    new.save_slots[1].player_lives[2] = 9;
+
+    assert_eq!(
+        diff(&old, &new),
+        vec![Change {
+            path: String::from("slot[1].player_lives[2]"),
+            label: String::from("Player 3"),
+            old: STARTING_LIVES.to_string(),
+            new: String::from("9"),
+        }]
+    );
+}
+
+#[test]
+fn stage_flags_are_reported_per_bit() {
+    let old = started();
+    let mut new = old.clone();
+    new.save_slots[1].stage_completion_flags[0][1] =
+        (StageCompletionFlags::GoalNormal | StageCompletionFlags::StarCoin3).bits() | 0x4000;
+
+    let changes = diff(&old, &new);
+    let paths: Vec<&str> = changes.iter().map(|c| c.path.as_str()).collect();
+
+    assert_eq!(
+        paths,
+        vec![
+            "slot[1].stage[0][1].StarCoin3",
+            "slot[1].stage[0][1].GoalNormal",
+            "slot[1].stage[0][1].0x4000",
+        ]
+    );
+    assert!(changes.iter().all(|c| c.label == "1-2" && c.new == "true"));
+}
@@ -0,0 +1,74 @@
+use penguin::savefile::{constants::*, fields::FieldKind, SaveFile};
+
+const KINDS: [FieldKind; 12] = [
+    FieldKind::U8,
+    FieldKind::U16,
+    FieldKind::U32,
+    FieldKind::Bool,
+    FieldKind::Region,
+    FieldKind::Character,
+    FieldKind::Powerup,
+    FieldKind::MushroomHouse,
+    FieldKind::EnemyDirection,
+    FieldKind::GameFlags,
+    FieldKind::SpawnFlags,
+    FieldKind::StageFlags,
+];
+
+#[test]
+fn format_then_parse_gives_the_same_value() {
+    for kind in KINDS {
+        let values: Vec<u32> = match kind {
+            FieldKind::Bool => vec![0, 1],
+            FieldKind::Region => SaveFileRegion::ALL
+                .iter()
+                .map(|r| r.code() as u32)
+                .collect(),
+            _ => vec![0, 1, 2, 3, 0x21, 0x7F, kind.max()],
+        };
+
+        for value in values {
+            let text = kind.format(value);
+
+            assert_eq!(kind.parse(&text), Some(value), "{:?}: {:?}", kind, text);
+        }
+    }
+}
+
+#[test]
+fn parse_rejects_values_out_of_range() {
+    assert_eq!(FieldKind::U8.parse("256"), None);
+    assert_eq!(FieldKind::Bool.parse("2"), None);
+    assert_eq!(FieldKind::Region.parse("Mars"), None);
+}
+
+#[test]
+fn set_field_then_get_field_gives_the_same_value() {
+    let mut file = SaveFile::new(SaveFileRegion::NTSC);
+
+    file.set_field("slot[1].player_lives[2]", "42").unwrap();
+    file.set_field(
+        "slot[0].stage_completion_flags[3][21]",
+        "GoalNormal | StarCoin2",
+    )
+    .unwrap();
+
+    assert_eq!(file.save_slots[1].player_lives[2], 42);
+    assert_eq!(file.get_field("slot[1].player_lives[2]").unwrap().value, 42);
+    assert_eq!(
+        file.get_field("slot[0].stage_completion_flags[3][21]")
+            .unwrap()
+            .value,
+        (StageCompletionFlags::GoalNormal | StageCompletionFlags::StarCoin2).bits()
+    );
+}
+
+#[test]
+fn bad_paths_are_errors() {
+    let mut file = SaveFile::new(SaveFileRegion::NTSC);
+
+    assert!(file.get_field("slot[6].player_lives[0]").is_err());
+    assert!(file.get_field("slot[0].player_lives[4]").is_err());
+    assert!(file.get_field("slot[0].no_such_field").is_err());
+    assert!(file.set_field("slot[0].player_lives[0]", "lots").is_err());
+}
@@ -0,0 +1,45 @@
+use penguin::savefile::{
+    constants::*, presets::builtin_presets, savediff::diff, savejson::JsonError, SaveFile,
+};
+
+fn sample() -> SaveFile {
+    let presets = builtin_presets();
+    let mut file = SaveFile::new(SaveFileRegion::PAL);
+
+    file.apply_preset(0, &presets[3]).unwrap();
+    file.start_new_game(1).unwrap();
+
+    let slot = &mut file.save_slots[1];
+    slot.player_character[3] = PlayerCharacter::Unknown(9);
+    slot.player_lives[0] = 42;
+    slot.stage_completion_flags[2][20] = 0x8000_0031;
+
+    file.header.free_mode_play_count[1][4] = 300;
+
+    file
+}
+
+#[test]
+fn export_then_import_keeps_every_field() {
+    let file = sample();
+    let imported = SaveFile::from_json(&file.to_json_string()).expect("export should import");
+
+    assert_eq!(diff(&file, &imported), vec![]);
+    assert!(imported.to_json() == file.to_json());
+}
+
+#[test]
+fn import_rejects_other_formats_and_newer_versions() {
+    assert!(matches!(
+        SaveFile::from_json(r#"{"format": "something else", "format_version": 1}"#),
+        Err(JsonError::Invalid { .. })
+    ));
+
+    let mut json = sample().to_json();
+    json["format_version"] = 9999.into();
+
+    assert!(matches!(
+        SaveFile::from_json(&json.dump()),
+        Err(JsonError::UnsupportedVersion(9999))
+    ));
+}
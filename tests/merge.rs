@@ -0,0 +1,106 @@
+use penguin::savefile::{
+    constants::*,
+    savemerge::{merge, MergeStrategy},
+    SaveFile,
+};
+
+fn base() -> SaveFile {
+    let mut file = SaveFile::new(SaveFileRegion::NTSC);
+    file.start_new_game(0).unwrap();
+    file.header.free_mode_play_count[0][0] = 10;
+
+    file
+}
+
+#[test]
+fn progress_on_both_sides_is_combined() {
+    let base = base();
+    let mut ours = base.clone();
+    let mut theirs = base.clone();
+
+    ours.save_slots[0].stage_completion_flags[0][0] = StageCompletionFlags::GoalNormal.bits();
+    ours.save_slots[0].world_unlocked[1] = true;
+    theirs.save_slots[0].stage_completion_flags[0][0] = StageCompletionFlags::StarCoin1.bits();
+    theirs.save_slots[0].hint_movie_bought[3] = true;
+
+    let result = merge(&base, &ours, &theirs, MergeStrategy::Ours);
+    let slot = &result.merged.save_slots[0];
+
+    assert!(result.conflicts.is_empty());
+    assert_eq!(
+        slot.stage_completion_flags[0][0],
+        (StageCompletionFlags::GoalNormal | StageCompletionFlags::StarCoin1).bits()
+    );
+    assert!(slot.world_unlocked[1]);
+    assert!(slot.hint_movie_bought[3]);
+}
+
+#[test]
+fn play_counts_keep_both_increments() {
+    let base = base();
+    let mut ours = base.clone();
+    let mut theirs = base.clone();
+
+    ours.header.free_mode_play_count[0][0] = 13;
+    theirs.header.free_mode_play_count[0][0] = 15;
+
+    let result = merge(&base, &ours, &theirs, MergeStrategy::Ours);
+
+    assert!(result.conflicts.is_empty());
+    assert_eq!(result.merged.header.free_mode_play_count[0][0], 18);
+}
+
+#[test]
+fn the_empty_flag_only_stays_if_both_sides_kept_it() {
+    let base = SaveFile::new(SaveFileRegion::NTSC);
+    let mut ours = base.clone();
+    let mut theirs = base.clone();
+
+    ours.start_new_game(2).unwrap();
+    theirs.save_slots[2].game_completion_flags |= GameCompletionFlags::FinalBossBeaten.bits();
+
+    let result = merge(&base, &ours, &theirs, MergeStrategy::Ours);
+
+    assert_eq!(
+        result.merged.save_slots[2].game_completion_flags,
+        GameCompletionFlags::FinalBossBeaten.bits()
+    );
+}
+
+#[test]
+fn other_fields_conflict_and_follow_the_strategy() {
+    let base = base();
+    let mut ours = base.clone();
+    let mut theirs = base.clone();
+
+    ours.save_slots[0].player_lives[0] = 20;
+    theirs.save_slots[0].player_lives[0] = 30;
+
+    for (strategy, expected) in [
+        (MergeStrategy::Ours, 20),
+        (MergeStrategy::Theirs, 30),
+        (MergeStrategy::Base, STARTING_LIVES),
+        (MergeStrategy::Highest, 30),
+    ] {
+        let result = merge(&base, &ours, &theirs, strategy);
+
+        assert_eq!(result.merged.save_slots[0].player_lives[0], expected);
+        assert_eq!(result.conflicts.len(), 1);
+        assert_eq!(result.conflicts[0].path, "slot[0].player_lives[0]");
+        assert_eq!(result.conflicts[0].resolved, expected.to_string());
+    }
+}
+
+#[test]
+fn changes_on_one_side_are_taken() {
+    let base = base();
+    let ours = base.clone();
+    let mut theirs = base.clone();
+
+    theirs.save_slots[0].player_coins[1] = 77;
+
+    let result = merge(&base, &ours, &theirs, MergeStrategy::Ours);
+
+    assert!(result.conflicts.is_empty());
+    assert_eq!(result.merged.save_slots[0].player_coins[1], 77);
+}
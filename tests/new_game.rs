@@ -0,0 +1,96 @@
+use penguin::savefile::{
+    checksum::CrcReport,
+    constants::*,
+    savediff::{diff, render_text},
+    saveslot::SaveSlot,
+    SaveFile,
+};
+
+// a save written by the game right after starting a new file in Save Slot 1.
+// none has been captured yet, so the comparison below is ignored until one is added
+// and the other tests only check what is known about a fresh game
+const FRESH_SAVE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/new_game.sav");
+
+#[test]
+#[ignore = "needs a fresh save captured from the game at tests/fixtures/new_game.sav"]
+fn new_game_matches_a_fresh_save_from_the_game() {
+    let captured = SaveFile::from_path(std::path::Path::new(FRESH_SAVE)).unwrap();
+
+    let mut generated = captured.clone();
+    generated.save_slots[0] = SaveSlot::new_game();
+
+    // only the slot is compared; the header holds settings the game keeps across files
+    assert!(
+        generated.save_slots[0].to_bytes() == captured.save_slots[0].to_bytes(),
+        "the new game slot differs from the game's:\n{}",
+        render_text(&diff(&captured, &generated))
+    );
+}
+
+#[test]
+fn new_game_starts_in_world_1() {
+    let slot = SaveSlot::new_game();
+
+    assert!(!slot.is_empty());
+    assert_eq!(slot.world_unlocked, {
+        let mut unlocked = [false; WORLD_COUNT];
+        unlocked[0] = true;
+        unlocked
+    });
+    assert_eq!(
+        (slot.cur_world, slot.cur_subworld, slot.cur_path_node),
+        (0, 0, 0)
+    );
+    assert_eq!(slot.player_lives, [STARTING_LIVES; PLAYER_COUNT]);
+    assert_eq!(slot.stage_completion_flags, [[0; STAGE_COUNT]; WORLD_COUNT]);
+}
+
+#[test]
+fn new_game_has_no_ambush_enemy_placed() {
+    let slot = SaveSlot::new_game();
+
+    assert!(slot
+        .enemy_walk_direction
+        .iter()
+        .flatten()
+        .all(|d| *d == EnemyDirection::FirstTimeValue));
+}
+
+#[test]
+fn new_game_has_no_problems() {
+    let mut file = SaveFile::new(SaveFileRegion::NTSC);
+    file.start_new_game(0).unwrap();
+
+    assert_eq!(file.validate(), vec![]);
+    assert!(file.check_consistency().is_empty());
+}
+
+#[test]
+fn a_new_file_has_every_slot_empty() {
+    let file = SaveFile::new(SaveFileRegion::NTSC);
+
+    assert!(file.save_slots.iter().all(|slot| slot.is_empty()));
+}
+
+#[test]
+fn new_files_have_valid_checksums() {
+    let mut file = SaveFile::new(SaveFileRegion::NTSC);
+    file.start_new_game(0).unwrap();
+
+    let bytes = file.to_bytes();
+
+    assert_eq!(bytes.len(), SAVE_FILE_SIZE);
+    assert!(CrcReport::from_bytes(&bytes).unwrap().is_valid());
+}
+
+#[test]
+fn new_game_survives_a_round_trip() {
+    let mut file = SaveFile::new(SaveFileRegion::PAL);
+    file.start_new_game(2).unwrap();
+
+    let bytes = file.to_bytes();
+    let reread = SaveFile::from_bytes(&bytes).unwrap();
+
+    assert_eq!(reread.header.last_selected_index, 2);
+    assert_eq!(reread.to_bytes(), bytes);
+}
@@ -0,0 +1,94 @@
+use penguin::savefile::{
+    constants::*,
+    presets::{builtin_presets, load_presets},
+    SaveFile,
+};
+
+#[test]
+fn builtin_presets_are_consistent() {
+    for preset in builtin_presets() {
+        let mut file = SaveFile::new(SaveFileRegion::NTSC);
+        file.apply_preset(1, &preset).unwrap();
+
+        assert!(
+            file.check_consistency().is_empty(),
+            "{}: {:?}",
+            preset.name,
+            file.check_consistency()
+        );
+        assert!(file.validate().is_empty(), "{}", preset.name);
+    }
+}
+
+#[test]
+fn full_completion_sets_every_derived_flag() {
+    let presets = builtin_presets();
+    let preset = presets.iter().find(|p| p.name == "100%").unwrap();
+
+    let mut file = SaveFile::new(SaveFileRegion::PAL);
+    file.apply_preset(0, preset).unwrap();
+
+    let flags = GameCompletionFlags::from_bits_retain(file.save_slots[0].game_completion_flags);
+
+    assert!(flags.contains(
+        GameCompletionFlags::FinalBossBeaten
+            | GameCompletionFlags::AllGoals
+            | GameCompletionFlags::AllStarCoinsReg
+            | GameCompletionFlags::AllStarCoinsSpe
+            | GameCompletionFlags::GameCompleted
+    ));
+    assert_eq!(file.header.extra_modes_unlocked_worlds, 0x1FF);
+}
+
+// counts from the game rather than the stage tables, so a missing course shows up
+#[test]
+fn full_completion_clears_every_course() {
+    let presets = builtin_presets();
+    let preset = presets.iter().find(|p| p.name == "100%").unwrap();
+
+    let mut file = SaveFile::new(SaveFileRegion::NTSC);
+    file.apply_preset(0, preset).unwrap();
+
+    let flags = &file.save_slots[0].stage_completion_flags;
+    let count = |worlds: std::ops::Range<usize>, flag: StageCompletionFlags| -> usize {
+        worlds
+            .flat_map(|w| flags[w].iter())
+            .filter(|&&f| f & flag.bits() != 0)
+            .count()
+    };
+
+    let coins = |worlds: std::ops::Range<usize>| -> usize {
+        [
+            StageCompletionFlags::StarCoin1,
+            StageCompletionFlags::StarCoin2,
+            StageCompletionFlags::StarCoin3,
+        ]
+        .into_iter()
+        .map(|coin| count(worlds.clone(), coin))
+        .sum()
+    };
+
+    assert_eq!(count(0..8, StageCompletionFlags::GoalNormal), 69);
+    assert_eq!(coins(0..8), 207);
+
+    assert_eq!(
+        count(0..ACTUAL_WORLD_COUNT, StageCompletionFlags::GoalNormal),
+        77
+    );
+    assert_eq!(coins(0..ACTUAL_WORLD_COUNT), 231);
+
+    for (stage, f) in flags[0].iter().take(6).enumerate() {
+        assert_ne!(*f, 0, "1-{} isn't cleared", stage + 1);
+    }
+}
+
+#[test]
+fn user_presets_are_checked() {
+    let presets = load_presets(r#"[{"name": "W4", "cleared_worlds": 3, "star_coins": true}]"#)
+        .expect("preset should load");
+
+    assert_eq!(presets[0].current_world(), 3);
+
+    assert!(load_presets(r#"[{"name": "Bad", "final_boss_beaten": true}]"#).is_err());
+    assert!(load_presets(r#"[{"name": "Locked", "current_world": 5}]"#).is_err());
+}
@@ -0,0 +1,55 @@
+use penguin::savefile::{
+    checksum::repair_checksums,
+    constants::{
+        PlayerCharacter, HEADER_SIZE, SAVE_FILE_SIZE, SAVE_SLOT_COUNT, SAVE_SLOT_SIZE, SAVE_VERSION,
+    },
+    SaveFile,
+};
+
+// every byte filled with noise, so unknown bytes, unknown enum values and odd
+// flag bits are all non-zero; only what the parser insists on is fixed up
+fn noisy_file(trailing: &[u8]) -> Vec<u8> {
+    let mut state: u32 = 0x1234_5678;
+    let mut bytes: Vec<u8> = (0..SAVE_FILE_SIZE)
+        .map(|_| {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            (state >> 24) as u8
+        })
+        .collect();
+
+    bytes[..4].copy_from_slice(b"SMNE");
+    bytes[4] = SAVE_VERSION;
+
+    for i in 0..SAVE_SLOT_COUNT {
+        bytes[HEADER_SIZE + SAVE_SLOT_SIZE * i] = SAVE_VERSION;
+    }
+
+    // a character the game doesn't have, for the first player of the first slot
+    bytes[HEADER_SIZE + 0x2A] = 0x7F;
+
+    repair_checksums(&mut bytes).unwrap();
+    bytes.extend_from_slice(trailing);
+
+    bytes
+}
+
+#[test]
+fn open_then_save_is_byte_identical() {
+    let bytes = noisy_file(b"trailing data the game doesn't read");
+    let file = SaveFile::from_bytes(&bytes).unwrap();
+
+    assert!(matches!(
+        file.save_slots[0].player_character[0],
+        PlayerCharacter::Unknown(0x7F)
+    ));
+    assert!(file.to_bytes() == bytes);
+}
+
+#[test]
+fn round_trip_survives_a_second_pass() {
+    let bytes = noisy_file(&[]);
+    let once = SaveFile::from_bytes(&bytes).unwrap().to_bytes();
+    let twice = SaveFile::from_bytes(&once).unwrap().to_bytes();
+
+    assert!(twice == bytes);
+}
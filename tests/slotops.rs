@@ -0,0 +1,70 @@
+use penguin::savefile::{constants::*, saveslot::SaveSlot, slotops::SlotError, SaveFile};
+
+fn file() -> SaveFile {
+    let mut file = SaveFile::new(SaveFileRegion::NTSC);
+    file.start_new_game(0).unwrap();
+    file.save_slots[0].player_lives[0] = 11;
+
+    file
+}
+
+#[test]
+fn copy_makes_an_identical_slot() {
+    let mut file = file();
+    file.copy_slot(0, 2).unwrap();
+
+    assert!(file.save_slots[2] == file.save_slots[0]);
+    assert_eq!(file.copy_slot(0, 6), Err(SlotError::InvalidIndex(6)));
+}
+
+#[test]
+fn swap_moves_the_last_selected_slot_along() {
+    let mut file = file();
+    file.swap_slots(0, 1).unwrap();
+
+    assert!(file.save_slots[0].is_empty());
+    assert_eq!(file.save_slots[1].player_lives[0], 11);
+    assert_eq!(file.header.last_selected_index, 1);
+}
+
+#[test]
+fn clear_empties_the_slot_and_moves_the_last_selected_slot() {
+    let mut file = file();
+    file.start_new_game(2).unwrap();
+    file.clear_slot(2).unwrap();
+
+    assert!(file.save_slots[2] == SaveSlot::empty());
+    assert_eq!(file.header.last_selected_index, 0);
+}
+
+#[test]
+fn promoting_a_quick_save_replaces_its_normal_slot() {
+    let mut file = file();
+
+    let mut quick = file.save_slots[0].clone();
+    quick.player_lives[0] = 22;
+    file.save_slots[NORMAL_SLOT_COUNT + 1] = quick.clone();
+
+    file.promote_quick_save(NORMAL_SLOT_COUNT + 1).unwrap();
+
+    assert!(file.save_slots[1] == quick);
+    assert!(file.save_slots[NORMAL_SLOT_COUNT + 1] == SaveSlot::empty());
+    assert_eq!(file.header.last_selected_index, 1);
+
+    assert_eq!(file.promote_quick_save(0), Err(SlotError::NotQuickSlot(0)));
+}
+
+#[test]
+fn promoting_an_empty_quick_save_is_rejected() {
+    let mut file = file();
+    file.save_slots[1] = file.save_slots[0].clone();
+    file.save_slots[NORMAL_SLOT_COUNT + 1] = SaveSlot::empty();
+
+    let before = file.clone();
+
+    assert_eq!(
+        file.promote_quick_save(NORMAL_SLOT_COUNT + 1),
+        Err(SlotError::EmptySlot(NORMAL_SLOT_COUNT + 1))
+    );
+    assert!(file == before);
+}
@@ -0,0 +1,23 @@
+use penguin::savefile::{constants::*, stages::world_courses};
+
+fn courses(worlds: std::ops::Range<usize>) -> usize {
+    worlds.map(|w| world_courses(w).len()).sum()
+}
+
+#[test]
+fn course_counts_match_the_star_coin_total() {
+    assert_eq!(courses(0..8), 69);
+    assert_eq!(courses(0..8) * 3, 207);
+
+    assert_eq!(courses(0..ACTUAL_WORLD_COUNT), 77);
+    assert_eq!(courses(0..ACTUAL_WORLD_COUNT) * 3, 231);
+}
+
+#[test]
+fn world_1_has_six_numbered_courses() {
+    let stages: Vec<usize> = world_courses(0).iter().map(|c| c.stage).collect();
+
+    for stage in 0..6 {
+        assert!(stages.contains(&stage), "1-{} is missing", stage + 1);
+    }
+}
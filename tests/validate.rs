@@ -0,0 +1,50 @@
+use penguin::savefile::{constants::*, savevalidate::Severity, SaveFile};
+
+#[test]
+fn a_new_game_has_no_problems() {
+    let mut file = SaveFile::new(SaveFileRegion::NTSC);
+    file.start_new_game(0).unwrap();
+
+    assert_eq!(file.validate(), vec![]);
+}
+
+#[test]
+fn a_bad_slot_reports_each_field() {
+    let mut file = SaveFile::new(SaveFileRegion::NTSC);
+    file.start_new_game(1).unwrap();
+
+    let slot = &mut file.save_slots[1];
+    slot.cur_world = 12;
+    slot.player_lives[2] = 120;
+    slot.player_character[3] = PlayerCharacter::Mario;
+    slot.stage_completion_flags[0][0] = 0x8000;
+    slot.ingame_score = 125;
+
+    let found: Vec<(String, Severity)> = file
+        .validate()
+        .into_iter()
+        .map(|p| (p.path(), p.severity))
+        .collect();
+
+    assert_eq!(
+        found,
+        vec![
+            (String::from("slot[1].cur_world"), Severity::Error),
+            (String::from("slot[1].ingame_score"), Severity::Warning),
+            (String::from("slot[1].player_lives[2]"), Severity::Error),
+            (String::from("slot[1].player_character[3]"), Severity::Error),
+            (
+                String::from("slot[1].stage_completion_flags[0][0]"),
+                Severity::Warning
+            ),
+        ]
+    );
+}
+
+#[test]
+fn empty_slots_are_skipped() {
+    let mut file = SaveFile::new(SaveFileRegion::NTSC);
+    file.save_slots[4].player_lives[0] = 200;
+
+    assert_eq!(file.validate(), vec![]);
+}